@@ -0,0 +1,179 @@
+use crate::object_store::{
+    list_files, FileFilter, IntegrityIssues, Manifest, ManifestEntry, ObjectStore,
+    MANIFEST_FILE_NAME,
+};
+use crate::progress::Progress;
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+/// 圧縮アーカイブ形式の世代ファイルの拡張子
+pub const ARCHIVE_EXTENSION: &str = "zip";
+// アーカイブ内でセーブデータ本体を格納するフォルダ名（manifest.json との名前衝突を避けるため）
+const DATA_PREFIX: &str = "data/";
+
+/// 1世代を1つの zip ファイルとして保存する形式。
+/// アーカイブ直下に manifest.json、data/ 配下にセーブデータ本体を格納します。
+pub struct ArchiveStore;
+
+impl ArchiveStore {
+    /// 指定したパスが、この形式の世代ファイルかどうか
+    pub fn is_archive(path: &Path) -> bool {
+        path.is_file() && path.extension().is_some_and(|ext| ext == ARCHIVE_EXTENSION)
+    }
+
+    /// src 配下のファイル（include が true のもの）を archive_path に圧縮して書き出す。
+    /// 取り消された場合や失敗した場合は、書き込み途中のアーカイブを削除します。
+    pub fn create(
+        src: &Path,
+        archive_path: &Path,
+        include: FileFilter,
+        progress: &Progress,
+    ) -> io::Result<Manifest> {
+        if let Some(parent) = archive_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        // 書き込み途中のアーカイブが世代として認識されないよう、一時ファイルに書いてからリネーム
+        let tmp_path = archive_path.with_extension("tmp");
+        let result = Self::write_archive(src, &tmp_path, include, progress);
+        match result {
+            Ok(manifest) => {
+                fs::rename(&tmp_path, archive_path)?;
+                Ok(manifest)
+            }
+            Err(e) => {
+                let _ = fs::remove_file(&tmp_path);
+                Err(e)
+            }
+        }
+    }
+
+    fn write_archive(
+        src: &Path,
+        archive_path: &Path,
+        include: FileFilter,
+        progress: &Progress,
+    ) -> io::Result<Manifest> {
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        let mut zip = ZipWriter::new(fs::File::create(archive_path)?);
+        let mut manifest = Manifest::default();
+
+        let files = list_files(src, include)?;
+        progress.start(files.len() as u64, files.iter().map(|file| file.size).sum());
+
+        for file in files {
+            progress.check()?;
+            let content = fs::read(&file.path)?;
+            zip.start_file(format!("{}{}", DATA_PREFIX, file.rel_path), options)?;
+            zip.write_all(&content)?;
+
+            progress.advance(file.size);
+            manifest.files.push(ManifestEntry {
+                path: file.rel_path,
+                hash: ObjectStore::hash_bytes(&content),
+                size: content.len() as u64,
+            });
+        }
+
+        let json = serde_json::to_string_pretty(&manifest)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        zip.start_file(MANIFEST_FILE_NAME, options)?;
+        zip.write_all(json.as_bytes())?;
+        zip.finish()?;
+
+        Ok(manifest)
+    }
+
+    /// アーカイブに埋め込まれた manifest.json を読み込む
+    pub fn read_manifest(archive_path: &Path) -> io::Result<Manifest> {
+        let mut zip = ZipArchive::new(fs::File::open(archive_path)?)?;
+        let mut content = String::new();
+        zip.by_name(MANIFEST_FILE_NAME)?
+            .read_to_string(&mut content)?;
+        serde_json::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// アーカイブ内のセーブデータを dest へ展開する
+    pub fn extract(archive_path: &Path, dest: &Path, progress: &Progress) -> io::Result<()> {
+        let mut zip = ZipArchive::new(fs::File::open(archive_path)?)?;
+        fs::create_dir_all(dest)?;
+
+        // 進捗の合計（展開後のサイズ）はアーカイブの目次から求める
+        let (mut files_total, mut bytes_total) = (0, 0);
+        for i in 0..zip.len() {
+            let file = zip.by_index_raw(i)?;
+            if !file.is_dir() && file.name().starts_with(DATA_PREFIX) {
+                files_total += 1;
+                bytes_total += file.size();
+            }
+        }
+        progress.start(files_total, bytes_total);
+
+        for i in 0..zip.len() {
+            progress.check()?;
+            let mut file = zip.by_index(i)?;
+            if file.is_dir() || !file.name().starts_with(DATA_PREFIX) {
+                continue;
+            }
+            // enclosed_name はルート外を指すパスを拒否する
+            let Some(enclosed) = file.enclosed_name() else {
+                println!("不正なパスをスキップしました: {}", file.name());
+                continue;
+            };
+            let rel_path = enclosed
+                .strip_prefix(DATA_PREFIX.trim_end_matches('/'))
+                .unwrap();
+            let dest_path = dest.join(rel_path);
+            if let Some(parent) = dest_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            let mut out = fs::File::create(dest_path)?;
+            let size = io::copy(&mut file, &mut out)?;
+            progress.advance(size);
+        }
+        Ok(())
+    }
+
+    /// アーカイブ内の各ファイルを展開・再ハッシュし、埋め込まれた manifest.json と突き合わせる
+    pub fn verify(archive_path: &Path) -> io::Result<IntegrityIssues> {
+        let manifest = Self::read_manifest(archive_path)?;
+        let mut expected: HashMap<String, ManifestEntry> = manifest
+            .files
+            .into_iter()
+            .map(|f| (f.path.clone(), f))
+            .collect();
+
+        let mut zip = ZipArchive::new(fs::File::open(archive_path)?)?;
+        let mut issues = IntegrityIssues::default();
+
+        for i in 0..zip.len() {
+            let mut file = zip.by_index(i)?;
+            let Some(path) = file.name().strip_prefix(DATA_PREFIX).map(|p| p.to_string()) else {
+                continue;
+            };
+            if file.is_dir() {
+                continue;
+            }
+            let Some(entry) = expected.remove(&path) else {
+                issues.extra.push(path);
+                continue;
+            };
+            // CRC 不一致などで展開できない場合も破損として扱う
+            let mut content = Vec::new();
+            let intact = file.read_to_end(&mut content).is_ok()
+                && content.len() as u64 == entry.size
+                && ObjectStore::hash_bytes(&content) == entry.hash;
+            if !intact {
+                issues.corrupted.push(path);
+            }
+        }
+
+        issues.missing.extend(expected.into_keys());
+        issues.missing.sort();
+        Ok(issues)
+    }
+}
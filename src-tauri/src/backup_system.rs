@@ -1,24 +1,94 @@
-use crate::settings_manager::AppSettings;
-use chrono::{DateTime, Local};
-use notify::{Config, Event, RecommendedWatcher, RecursiveMode, Watcher};
+use crate::archive_store::{ArchiveStore, ARCHIVE_EXTENSION};
+use crate::error::Error;
+use crate::file_stability;
+use crate::generation::{
+    copy_dir_recursive, Generation, GenerationMeta, GenerationOrigin, IntegrityStatus, VerifyReport,
+};
+use crate::job_queue::{JobKind, JobQueue, QueueState};
+use crate::object_store::{to_manifest_path, ObjectStore};
+use crate::path_filter::PathFilter;
+use crate::progress::{self, Operation, Progress};
+use crate::quota::{self, Footprint};
+use crate::retention::RetentionPolicy;
+use crate::settings_manager::{
+    AppSettings, SettingsManager, STORAGE_FORMAT_ARCHIVE, WATCHER_MODE_NATIVE, WATCHER_MODE_POLL,
+};
+use crate::subfolder_meta::{SubfolderMeta, SubfolderOverrides};
+use crate::trash::{Trash, TrashEntry};
+use crate::watcher_health::{WatcherHealth, WatcherState};
+use chrono::{DateTime, Local, TimeZone};
+use notify::{Config, Event, PollWatcher, RecommendedWatcher, RecursiveMode, Watcher};
 use serde::Serialize;
 use std::collections::HashMap;
 use std::fs;
+use std::io;
 use std::path::{Path, PathBuf};
+use std::sync::mpsc::Sender;
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
 use tauri::{AppHandle, Emitter};
 use walkdir::WalkDir;
 
-const BACKUPS_DIR_NAME: &str = "Backups";
-
 #[derive(Serialize, Clone, Debug)]
 pub struct BackupEntry {
-    /// フォルダ名としても使用されるタイムスタンプ形式の文字列（例: "20231024_153000"）
+    /// フォルダ名としても使用される世代ID（例: "20231024_153000_123"。旧形式の世代は "20231024_153000"）
     pub timestamp: String,
-    /// ソートや比較に利用するための数値型タイムスタンプ（将来的な拡張用）
+    /// 世代の作成日時（UNIX 秒）。ソートや比較に利用します
     pub timestamp_raw: i64,
+    /// 最後に行った整合性チェックの結果（"verified" / "corrupt" / "unverified"）
+    pub integrity: IntegrityStatus,
+    /// 世代が作成されたきっかけ（"auto" / "manual" / "pre_restore" / "imported"、不明な場合は null）
+    pub origin: Option<GenerationOrigin>,
+    /// 世代に含まれるファイル数
+    pub file_count: usize,
+    /// 世代に含まれるファイルの合計サイズ（バイト）
+    pub total_size: u64,
+    /// この世代に付けられたメモ
+    pub memo: String,
+    /// この世代に付けられたタグ
+    pub tags: Vec<String>,
+    /// 固定（自動削除の対象外）されているかどうか
+    pub pinned: bool,
+    /// 書き込み完了を確認できないまま作成した世代かどうか
+    pub possibly_incomplete: bool,
+}
+
+impl BackupEntry {
+    /// 世代のメタ情報から一覧表示用のエントリを作成する。
+    /// ファイル数・サイズが未記録の旧世代は、この時点で集計してメタ情報へ書き戻します。
+    /// 一覧の表示用に世代を読み込む。
+    /// キュー外で実行されるため書き込みは行わず、ファイル数・サイズが未記録の世代はその場で集計します
+    /// （メタ情報への書き戻しは、起動時にキューで実行する backfill_generation_stats が行う）。
+    fn from_generation(generation: Generation) -> Self {
+        let mut meta = generation.load_meta();
+        if meta.file_count.is_none() || meta.total_size.is_none() {
+            if let Ok((count, size)) = generation.content_stats() {
+                meta.file_count = Some(count);
+                meta.total_size = Some(size);
+            }
+        }
+
+        Self {
+            timestamp_raw: generation.created_at_epoch(&meta),
+            integrity: meta.integrity,
+            origin: meta.origin,
+            file_count: meta.file_count.unwrap_or(0),
+            total_size: meta.total_size.unwrap_or(0),
+            memo: meta.memo,
+            tags: meta.tags,
+            pinned: meta.pinned,
+            possibly_incomplete: meta.possibly_incomplete,
+            timestamp: generation.id,
+        }
+    }
+}
+
+/// 整理方針のドライランで削除対象となった世代
+#[derive(Serialize, Clone, Debug)]
+pub struct RetentionCandidate {
+    pub subfolder: String,
+    pub timestamp: String,
 }
 
 #[derive(Serialize, Clone, Debug)]
@@ -31,75 +101,281 @@ pub struct SubFolderState {
     pub backups: Vec<BackupEntry>,
     /// 監視対象（Repo内の実体フォルダ）が現在存在するかどうかのフラグ
     pub source_exists: bool,
+    /// このサブフォルダで全体設定の代わりに使用する設定（meta.json から読み込まれる）
+    pub overrides: SubfolderOverrides,
 }
 
+/// 各フィールドは共有されるため、複製しても同じ監視システムを指します（監視スレッドからの再初期化に使用）
+#[derive(Clone)]
 pub struct BackupSystem {
     app_handle: AppHandle,
     settings: Arc<Mutex<AppSettings>>,
-    watcher: Arc<Mutex<Option<RecommendedWatcher>>>,
-    // サブフォルダごとの最終更新時刻を保持し、短時間の連続した変更を1つのバックアップにまとめる（デバウンス用）
-    debounce_map: Arc<Mutex<HashMap<String, DateTime<Local>>>>,
+    // ネイティブ（OS の変更通知）またはポーリングのウォッチャー
+    watcher: Arc<Mutex<Option<Box<dyn Watcher + Send>>>>,
+    // バックアップ・リストア・削除・整理を順に実行するキュー（待機中の自動バックアップは1つにまとめられる）
+    queue: JobQueue,
     // ファイル削除イベントを一時的に記録し、削除に伴う無関係なフォルダ更新イベントを無視するために使用
     delete_tracker: Arc<Mutex<HashMap<String, DateTime<Local>>>>,
     // リストア実行中に発生するファイルシステムイベントを無視するためのフラグ
     is_restoring: Arc<Mutex<bool>>,
+    // 監視の状態・エラーと、サブフォルダごとの直近のバックアップの結果
+    health: Arc<Mutex<WatcherHealth>>,
 }
 
 impl BackupSystem {
     pub fn new(app_handle: AppHandle, settings: AppSettings) -> Self {
         // バックアップディレクトリが存在することを確認
-        let exe_path = std::env::current_exe().unwrap_or(PathBuf::from("."));
-        let exe_dir = exe_path.parent().unwrap_or(Path::new("."));
-        let backups_root = exe_dir.join(BACKUPS_DIR_NAME);
-        let _ = fs::create_dir_all(&backups_root);
+        let backups_root = settings.resolve_backups_root();
+        if let Err(e) = fs::create_dir_all(&backups_root) {
+            // 作成できない場合も起動は続け、各操作のエラーとして画面へ通知する
+            println!("バックアップフォルダの作成に失敗しました: {:?}", e);
+        }
+        // 保管期間を過ぎたゴミ箱の項目を完全に削除
+        Self::purge_trash(&backups_root, settings.trash_retention_days);
 
-        Self {
+        let system = Self {
+            queue: JobQueue::new(app_handle.clone()),
             app_handle,
             settings: Arc::new(Mutex::new(settings)),
             watcher: Arc::new(Mutex::new(None)),
-            debounce_map: Arc::new(Mutex::new(HashMap::new())),
             delete_tracker: Arc::new(Mutex::new(HashMap::new())),
             is_restoring: Arc::new(Mutex::new(false)),
+            health: Arc::new(Mutex::new(Self::initial_health(&backups_root))),
+        };
+
+        // 時間を指定して一時停止したまま終了していた場合は、解除のタイマーを再開する（期限切れならすぐに再開）
+        let paused_until = {
+            let settings = system.settings.lock().unwrap();
+            (settings.auto_backup_paused && !settings.auto_backup_paused_until.is_empty())
+                .then(|| settings.auto_backup_paused_until.clone())
+        };
+        if let Some(until) = paused_until {
+            system.schedule_resume(until);
+        }
+
+        // ファイル数・サイズが未記録の世代（以前のバージョンで作成したもの）のメタ情報を補う。
+        // 固定・メモの更新と同時に書き込まないよう、キューのジョブとして実行する
+        let app = system.app_handle.clone();
+        system
+            .queue
+            .enqueue(JobKind::Annotate, None, Duration::ZERO, move || {
+                if Self::backfill_generation_stats(&backups_root) > 0 {
+                    Self::emit_state(&app, &backups_root);
+                }
+            });
+        system
+    }
+
+    /// ファイル数・サイズが未記録の世代を集計してメタ情報へ書き戻し、更新した世代の数を返す
+    fn backfill_generation_stats(backups_root: &Path) -> usize {
+        let mut updated = 0;
+        for subfolder in Self::list_backup_subfolders(backups_root) {
+            for generation in Generation::list(&backups_root.join(&subfolder)) {
+                let mut meta = generation.load_meta();
+                if meta.file_count.is_some() && meta.total_size.is_some() {
+                    continue;
+                }
+                let Ok((count, size)) = generation.content_stats() else {
+                    continue;
+                };
+                meta.file_count = Some(count);
+                meta.total_size = Some(size);
+                match generation.save_meta(&meta) {
+                    Ok(_) => updated += 1,
+                    Err(e) => println!(
+                        "世代のメタ情報の更新に失敗しました: {} ({:?})",
+                        generation.id, e
+                    ),
+                }
+            }
         }
+        updated
     }
 
     pub fn update_settings(&self, new_settings: AppSettings) {
-        {
+        let watch_target_changed = {
             let mut settings = self.settings.lock().unwrap();
+            let changed = settings.repo_save_path != new_settings.repo_save_path
+                || settings.watcher_mode != new_settings.watcher_mode
+                || settings.poll_interval_ms != new_settings.poll_interval_ms;
             *settings = new_settings;
+            changed
+        };
+        // 待ち時間などはイベントごとに設定を参照するため、ウォッチャーを再起動しなくても反映される。
+        // 監視パス・監視方式が変わった場合（または未起動の場合）のみ、ウォッチャーを再初期化
+        let watching = self.watcher.lock().unwrap().is_some();
+        if watch_target_changed || !watching {
+            self.start_watcher();
         }
-        // 新しいパスでウォッチャーを再初期化
-        self.start_watcher();
     }
 
     pub fn get_settings(&self) -> AppSettings {
         self.settings.lock().unwrap().clone()
     }
 
-    pub fn start_watcher(&self) {
-        let settings = self.settings.lock().unwrap();
-        let path_str = &settings.repo_save_path;
-        let path = Path::new(path_str);
+    /// 現在の設定に基づくバックアップの保存先ルート
+    pub fn backups_root(&self) -> PathBuf {
+        self.settings.lock().unwrap().resolve_backups_root()
+    }
 
-        if !path.exists() {
-            println!("リポジトリパスが存在しません: {}", path_str);
+    /// 自動バックアップを一時停止する。minutes を指定した場合は、その時間が経過すると自動的に再開します。
+    /// 手動バックアップ・リストアは一時停止中も実行できます。
+    /// 設定ファイルへ保存できなかった場合も一時停止は有効になり、再起動後に引き継がれないことをエラーとして返します。
+    pub fn pause_watching(&self, minutes: Option<u64>) -> Result<(), Error> {
+        let until =
+            minutes.map(|m| (Local::now() + chrono::Duration::minutes(m as i64)).to_rfc3339());
+        let saved = {
+            let mut settings = self.settings.lock().unwrap();
+            settings.auto_backup_paused = true;
+            settings.auto_backup_paused_until = until.clone().unwrap_or_default();
+            SettingsManager::new().save(&settings)
+        };
+        // 停止前に検知して待機中の変更もバックアップしない
+        self.queue.cancel_pending(JobKind::AutoBackup);
+
+        if let Some(until) = until {
+            self.schedule_resume(until);
+        }
+        self.emit_watcher_state();
+        saved
+    }
+
+    /// 一時停止を解除し、変更検知による自動バックアップを再開する（保存に失敗した場合の扱いは pause_watching と同じ）
+    pub fn resume_watching(&self) -> Result<(), Error> {
+        let saved = {
+            let mut settings = self.settings.lock().unwrap();
+            settings.auto_backup_paused = false;
+            settings.auto_backup_paused_until.clear();
+            SettingsManager::new().save(&settings)
+        };
+        self.emit_watcher_state();
+        saved
+    }
+
+    /// 指定した日時に一時停止を解除する。その間に再開・停止し直された場合は何もしません。
+    fn schedule_resume(&self, until: String) {
+        let Ok(deadline) = DateTime::parse_from_rfc3339(&until) else {
             return;
+        };
+        let wait = deadline
+            .with_timezone(&Local)
+            .signed_duration_since(Local::now())
+            .to_std()
+            .unwrap_or_default();
+        let system = self.clone();
+        thread::spawn(move || {
+            thread::sleep(wait);
+            let still_scheduled = {
+                let settings = system.settings.lock().unwrap();
+                settings.auto_backup_paused && settings.auto_backup_paused_until == until
+            };
+            if still_scheduled {
+                println!("一時停止の期限が過ぎたため、自動バックアップを再開します");
+                if let Err(e) = system.resume_watching() {
+                    println!("{}", e);
+                }
+            }
+        });
+    }
+
+    /// 既存の世代から、サブフォルダごとに最後にバックアップした日時を求める（リストア前スナップショットは除く）
+    fn initial_health(backups_root: &Path) -> WatcherHealth {
+        let mut health = WatcherHealth::default();
+        for subfolder in Self::list_backup_subfolders(backups_root) {
+            let latest = Generation::list(&backups_root.join(&subfolder))
+                .into_iter()
+                .rev()
+                .map(|g| (g.load_meta(), g))
+                .find(|(meta, _)| !meta.is_pre_restore());
+            if let Some((meta, generation)) = latest {
+                if let Some(at) = Local
+                    .timestamp_opt(generation.created_at_epoch(&meta), 0)
+                    .single()
+                {
+                    health.seed_last_success(&subfolder, at.to_rfc3339());
+                }
+            }
         }
+        health
+    }
+
+    /// 監視の状態（一時停止・エラー・サブフォルダごとの直近のバックアップの結果）
+    pub fn watcher_state(&self) -> WatcherState {
+        let settings = self.settings.lock().unwrap();
+        let state = self.health.lock().unwrap().state(&settings);
+        state
+    }
+
+    /// 監視の状態をフロントエンド（およびトレイメニュー）へ通知する
+    pub fn emit_watcher_state(&self) {
+        let _ = self.app_handle.emit("watcher-state", self.watcher_state());
+    }
 
+    /// emit_watcher_state と同様だが、設定のロックを保持している処理から呼び出すためのもの
+    fn emit_watcher_state_with(
+        app_handle: &AppHandle,
+        settings: &AppSettings,
+        health: &Arc<Mutex<WatcherHealth>>,
+    ) {
+        let state = health.lock().unwrap().state(settings);
+        let _ = app_handle.emit("watcher-state", state);
+    }
+
+    pub fn start_watcher(&self) {
+        let settings = self.settings.lock().unwrap();
+        let root = PathBuf::from(&settings.repo_save_path);
+
+        // セーブデータフォルダがまだ無い場合（ゲームの初回起動前など）は、存在する最も近い親フォルダを監視し、
+        // 作成された時点で監視対象を切り替える
+        let Some(watch_target) = nearest_existing_dir(&root) else {
+            println!("監視できるフォルダがありません: {}", root.display());
+            *self.watcher.lock().unwrap() = None;
+            self.report_watcher_error(
+                &settings,
+                format!("監視できるフォルダがありません: {}", root.display()),
+            );
+            return;
+        };
+        let root_exists = watch_target == root;
+        let targets = if root_exists {
+            // セーブデータフォルダ自体の削除・作り直しを検知できるよう、親フォルダも（直下のみ）監視する
+            let mut targets = vec![(root.clone(), RecursiveMode::Recursive)];
+            if let Some(parent) = root.parent().filter(|p| p.is_dir()) {
+                targets.push((parent.to_path_buf(), RecursiveMode::NonRecursive));
+            }
+            targets
+        } else {
+            println!(
+                "リポジトリパスが存在しないため、作成されるまで {} を監視します: {}",
+                watch_target.display(),
+                root.display()
+            );
+            vec![(watch_target.clone(), RecursiveMode::NonRecursive)]
+        };
+
+        let system = self.clone();
         let app_handle = self.app_handle.clone();
-        let debounce_map = self.debounce_map.clone();
+        let queue = self.queue.clone();
         let delete_tracker = self.delete_tracker.clone();
         let settings_clone = self.settings.clone();
         let restoring_lock = self.is_restoring.clone();
+        let health = self.health.clone();
 
         let (tx, rx) = std::sync::mpsc::channel();
-        let mut watcher = RecommendedWatcher::new(tx, Config::default()).unwrap();
-
-        // 指定されたパス配下のすべての変更（再帰的）を監視対象に登録
-        match watcher.watch(path, RecursiveMode::Recursive) {
-            Ok(_) => println!("監視を開始しました: {}", path_str),
-            Err(e) => println!("パスの監視に失敗しました: {:?}", e),
-        }
+        let watcher = match Self::create_watcher(&self.app_handle, &settings, &targets, tx) {
+            Ok((watcher, mode)) => {
+                self.health.lock().unwrap().set_started(mode, !root_exists);
+                Self::emit_watcher_state_with(&self.app_handle, &settings, &self.health);
+                watcher
+            }
+            Err(e) => {
+                println!("パスの監視に失敗しました: {:?}", e);
+                // 以前のパスの監視を続けないよう、既存のウォッチャーも破棄する
+                *self.watcher.lock().unwrap() = None;
+                self.report_watcher_error(&settings, format!("パスの監視に失敗しました: {}", e));
+                return;
+            }
+        };
 
         // ウォッチャーを保持して破棄されないようにする
         let mut w = self.watcher.lock().unwrap();
@@ -112,6 +388,27 @@ impl BackupSystem {
                     Ok(res) => {
                         match res {
                             Ok(event) => {
+                                // 監視中のエラーから回復した場合は、正常な状態に戻ったことを通知
+                                if health.lock().unwrap().clear_error() {
+                                    let settings = settings_clone.lock().unwrap();
+                                    Self::emit_watcher_state_with(&app_handle, &settings, &health);
+                                }
+
+                                // セーブデータフォルダが作成・削除された（監視すべきフォルダが変わった）場合は、
+                                // ウォッチャーを作り直してこのループを終了する
+                                if nearest_existing_dir(&root).as_ref() != Some(&watch_target) {
+                                    println!(
+                                        "リポジトリパスの状態が変わったため、監視をやり直します"
+                                    );
+                                    system.start_watcher();
+                                    Self::emit_state(&system.app_handle, &system.backups_root());
+                                    break;
+                                }
+                                // 親フォルダのみを監視している間は、セーブデータフォルダの作成以外に関心がない
+                                if !root_exists {
+                                    continue;
+                                }
+
                                 // リストア実行中（自身によるファイル変更）は無視して無限ループを防ぐ
                                 if *restoring_lock.lock().unwrap() {
                                     continue;
@@ -122,9 +419,8 @@ impl BackupSystem {
                                     EventKind::Remove(RemoveKind::Folder) => {
                                         // 監視対象のフォルダ自体が削除された場合、フロントエンドの表示を更新
                                         println!("フォルダが削除されました。状態を更新します。");
-                                        let exe_path = std::env::current_exe().unwrap_or_default();
                                         let backups_root =
-                                            exe_path.parent().unwrap().join("Backups");
+                                            settings_clone.lock().unwrap().resolve_backups_root();
                                         Self::emit_state(&app_handle, &backups_root);
 
                                         // 削除イベント発生時に親フォルダから「更新」イベントも飛んでくるため、それを除外するためにマーク
@@ -164,12 +460,31 @@ impl BackupSystem {
                                 Self::handle_fs_event(
                                     event,
                                     &app_handle,
-                                    &debounce_map,
+                                    &queue,
                                     &delete_tracker,
                                     &settings_clone,
+                                    &health,
                                 );
                             }
-                            Err(e) => println!("監視エラー: {:?}", e),
+                            Err(e) => {
+                                println!("監視エラー: {:?}", e);
+                                let settings = settings_clone.lock().unwrap();
+                                if health
+                                    .lock()
+                                    .unwrap()
+                                    .set_error(format!("監視エラー: {}", e), false)
+                                {
+                                    Self::send_notification(
+                                        &app_handle,
+                                        "監視エラー",
+                                        &format!(
+                                            "セーブデータの監視中にエラーが発生しました。自動バックアップが行われない可能性があります: {}",
+                                            e
+                                        ),
+                                    );
+                                }
+                                Self::emit_watcher_state_with(&app_handle, &settings, &health);
+                            }
                         }
                     }
                     Err(_) => break, // チャンネルがクローズされたらループ終了
@@ -178,6 +493,81 @@ impl BackupSystem {
         });
     }
 
+    /// 監視を開始できなかったことを記録し、画面へ通知する（エラー状態が続いている間は通知を繰り返さない）
+    fn report_watcher_error(&self, settings: &AppSettings, reason: String) {
+        if self.health.lock().unwrap().set_error(reason.clone(), true) {
+            Self::send_notification(&self.app_handle, "監視の開始に失敗", &reason);
+        }
+        Self::emit_watcher_state_with(&self.app_handle, settings, &self.health);
+    }
+
+    /// 設定された方式でウォッチャーを作成し、targets を監視対象に登録する。
+    /// 作成したウォッチャーと、実際に使用した監視方式を返します。
+    /// ネイティブの監視を開始できない場合（ネットワークドライブや一部のファイルシステムなど）は、
+    /// ポーリングによる監視へ自動的に切り替えます。
+    fn create_watcher(
+        app_handle: &AppHandle,
+        settings: &AppSettings,
+        targets: &[(PathBuf, RecursiveMode)],
+        tx: Sender<notify::Result<Event>>,
+    ) -> notify::Result<(Box<dyn Watcher + Send>, &'static str)> {
+        let path = &targets[0].0;
+        if settings.watcher_mode != WATCHER_MODE_POLL {
+            let native =
+                RecommendedWatcher::new(tx.clone(), Config::default()).and_then(|mut watcher| {
+                    Self::watch_targets(&mut watcher, targets)?;
+                    Ok(watcher)
+                });
+            match native {
+                Ok(watcher) => {
+                    println!("監視を開始しました: {}", path.display());
+                    return Ok((Box::new(watcher), WATCHER_MODE_NATIVE));
+                }
+                Err(e) => {
+                    println!(
+                        "ネイティブの監視を開始できないため、ポーリングに切り替えます: {:?}",
+                        e
+                    );
+                    Self::send_notification(
+                        app_handle,
+                        "ポーリング監視へ切り替え",
+                        "OS の変更通知を利用できないため、定期的な確認で変更を検知します",
+                    );
+                }
+            }
+        }
+
+        // 一定間隔でファイルを走査し、内容のハッシュを比較して変更を検知する
+        // （更新日時が正しく反映されないファイルシステムでも検知できるよう、内容も比較する）
+        let config = Config::default()
+            .with_poll_interval(Duration::from_millis(settings.poll_interval_ms))
+            .with_compare_contents(true);
+        let mut watcher = PollWatcher::new(tx, config)?;
+        Self::watch_targets(&mut watcher, targets)?;
+        println!(
+            "ポーリングによる監視を開始しました（{}ms 間隔）: {}",
+            settings.poll_interval_ms,
+            path.display()
+        );
+        Ok((Box::new(watcher), WATCHER_MODE_POLL))
+    }
+
+    /// 監視対象を登録する。最初の対象は必須とし、以降の対象（親フォルダなど）は登録できなくても続行する
+    fn watch_targets(
+        watcher: &mut dyn Watcher,
+        targets: &[(PathBuf, RecursiveMode)],
+    ) -> notify::Result<()> {
+        for (i, (path, mode)) in targets.iter().enumerate() {
+            if let Err(e) = watcher.watch(path, *mode) {
+                if i == 0 {
+                    return Err(e);
+                }
+                println!("{} の監視に失敗しました: {:?}", path.display(), e);
+            }
+        }
+        Ok(())
+    }
+
     fn mark_deletion(
         event: &Event,
         settings_lock: &Arc<Mutex<AppSettings>>,
@@ -200,39 +590,70 @@ impl BackupSystem {
     fn handle_fs_event(
         event: Event,
         app_handle: &AppHandle,
-        debounce_map: &Arc<Mutex<HashMap<String, DateTime<Local>>>>,
+        queue: &JobQueue,
         delete_tracker: &Arc<Mutex<HashMap<String, DateTime<Local>>>>,
         settings_lock: &Arc<Mutex<AppSettings>>,
+        health: &Arc<Mutex<WatcherHealth>>,
     ) {
         // RepoSavePath のサブフォルダ内での変更に関心がある
         // event.paths に変更されたファイルが含まれる。
         // RepoSavePath のどの直下サブフォルダが変更されたかを特定する必要がある。
 
         let settings = settings_lock.lock().unwrap();
+        // 一時停止中は変更を検知してもバックアップしない
+        if settings.is_auto_backup_paused() {
+            return;
+        }
         let repo_root = Path::new(&settings.repo_save_path);
+        let backups_root = settings.resolve_backups_root();
 
-        let mut affected_subfolders = Vec::new();
+        // サブフォルダ名と、そのサブフォルダ内で変更されたパス（サブフォルダからの相対パス）
+        let mut affected_subfolders: Vec<(String, Vec<String>)> = Vec::new();
 
         for path in event.paths {
             // Find relative path from repo_root
             if let Ok(rel_path) = path.strip_prefix(repo_root) {
                 // If the path is just the root itself, ignore (or handle if files are there, but req said subfolders)
-                if let Some(first_comp) = rel_path.components().next() {
+                let mut components = rel_path.components();
+                if let Some(first_comp) = components.next() {
                     let subfolder_name = first_comp.as_os_str().to_string_lossy().to_string();
-                    if !affected_subfolders.contains(&subfolder_name) {
-                        affected_subfolders.push(subfolder_name);
+                    // リストア用の作業フォルダなど、"." で始まるフォルダはバックアップ対象外
+                    if subfolder_name.starts_with('.') {
+                        continue;
+                    }
+                    let inner_path = to_manifest_path(components.as_path());
+                    match affected_subfolders
+                        .iter_mut()
+                        .find(|(name, _)| *name == subfolder_name)
+                    {
+                        Some((_, paths)) => paths.push(inner_path),
+                        None => affected_subfolders.push((subfolder_name, vec![inner_path])),
                     }
                 }
             }
         }
 
-        for folder in affected_subfolders {
+        for (folder, changed_paths) in affected_subfolders {
+            // サブフォルダごとの設定（自動バックアップの有無・対象ファイル・待ち時間）を確認
+            let overrides = SubfolderMeta::load(&backups_root.join(&folder)).overrides;
+            if !overrides.auto_backup_enabled() {
+                continue;
+            }
+            // 一時ファイルなど対象外のファイルのみの変更ではバックアップしない（サブフォルダ自体の変更は対象）
+            let filter = overrides.path_filter(&settings);
+            if changed_paths
+                .iter()
+                .all(|p| !p.is_empty() && !filter.includes(p))
+            {
+                continue;
+            }
+
             // CHECK: Was there a deletion recently?
             {
                 let tracker = delete_tracker.lock().unwrap();
                 if let Some(del_time) = tracker.get(&folder) {
                     let diff = Local::now().signed_duration_since(*del_time);
-                    if diff.num_milliseconds() < 500 {
+                    if diff.num_milliseconds() < settings.deletion_quiet_ms as i64 {
                         // 削除に伴う変更の可能性が高いため、この変更を無視
                         println!(
                             "最近の削除が原因と思われるため、{} の変更を無視しました",
@@ -243,115 +664,693 @@ impl BackupSystem {
                 }
             }
 
-            let mut map = debounce_map.lock().unwrap();
-            let now = Local::now();
-
-            // 変更イベントが発生してから 300ms 待機し、その間に新たな変更がなければバックアップを実行
+            // 変更イベントが発生してから待ち時間（全体設定、サブフォルダごとに変更可能）の間に
+            // 新たな変更がなければバックアップを実行（新たな変更があると待機中のジョブの待ち時間がリセットされる）
             // これにより、大量のファイルが短時間に連続して更新された際の負荷を抑えます
+            let debounce_ms = overrides.debounce_ms(&settings);
+            Self::schedule_auto_backup(
+                app_handle,
+                settings_lock,
+                health,
+                queue,
+                &folder,
+                Duration::from_millis(debounce_ms),
+            );
+        }
+    }
 
-            map.insert(folder.clone(), now);
-
-            let app_handle_clone = app_handle.clone();
-            let settings_clone = settings_lock.clone();
-            let debounce_map_clone = debounce_map.clone();
-            let folder_clone = folder.clone();
+    /// 自動バックアップのジョブを delay 後に実行するよう登録する。
+    /// 同じサブフォルダのジョブが待機中の場合は1つにまとめられ、最後の変更から delay 後に実行されます。
+    fn schedule_auto_backup(
+        app_handle: &AppHandle,
+        settings_lock: &Arc<Mutex<AppSettings>>,
+        health: &Arc<Mutex<WatcherHealth>>,
+        queue: &JobQueue,
+        folder: &str,
+        delay: Duration,
+    ) {
+        let app_handle = app_handle.clone();
+        let settings_lock = settings_lock.clone();
+        let health = health.clone();
+        let queue_clone = queue.clone();
+        let folder_clone = folder.to_string();
+
+        queue.enqueue(JobKind::AutoBackup, Some(folder), delay, move || {
+            let (folder_path, min_interval_secs) = {
+                let settings = settings_lock.lock().unwrap();
+                // 待機中に一時停止された場合はバックアップしない
+                if settings.is_auto_backup_paused() {
+                    return;
+                }
+                (
+                    settings.resolve_backups_root().join(&folder_clone),
+                    settings.min_backup_interval_minutes * 60,
+                )
+            };
+
+            // 前回のバックアップから最小間隔が経過していなければ、経過後に実行するよう登録し直す。
+            // 待機中にさらに変更があった場合も同じジョブにまとめられ、最後の状態だけを1世代にする
+            if let Some(wait) = Self::remaining_backup_interval(&folder_path, min_interval_secs) {
+                println!(
+                    "{} は前回のバックアップから間もないため、{} 秒後にバックアップします",
+                    folder_clone,
+                    wait.as_secs()
+                );
+                Self::schedule_auto_backup(
+                    &app_handle,
+                    &settings_lock,
+                    &health,
+                    &queue_clone,
+                    &folder_clone,
+                    wait,
+                );
+                return;
+            }
 
-            thread::spawn(move || {
-                thread::sleep(Duration::from_millis(300));
+            // 条件合致！バックアップを実行（結果は通知と watcher-state で伝えられる）
+            let _ = Self::perform_backup(
+                &app_handle,
+                &settings_lock,
+                &health,
+                &queue_clone,
+                &folder_clone,
+                GenerationOrigin::Auto,
+            );
+        });
+    }
 
-                let map = debounce_map_clone.lock().unwrap();
-                if let Some(last_time) = map.get(&folder_clone) {
-                    if *last_time == now {
-                        // 条件合致！バックアップを実行
-                        drop(map); // ロック解除
-                        Self::perform_backup(&app_handle_clone, &settings_clone, &folder_clone);
-                    }
-                }
-            });
+    /// 最新の世代の作成から最小間隔（秒）が経過するまでの残り時間。経過済み・制限なしの場合は None
+    fn remaining_backup_interval(folder_path: &Path, min_interval_secs: u64) -> Option<Duration> {
+        if min_interval_secs == 0 {
+            return None;
         }
+        let latest = Generation::list(folder_path).pop()?;
+        let created_at = latest.created_at_epoch(&latest.load_meta());
+        let elapsed = Local::now().timestamp() - created_at;
+        let remaining = min_interval_secs as i64 - elapsed;
+        (remaining > 0).then(|| Duration::from_secs(remaining as u64))
+    }
+
+    /// 手動バックアップをキューのジョブとして実行し、完了まで待って結果を返す
+    /// （実行中の処理が終わり次第、順番に実行される）
+    pub fn manual_backup(&self, subfolder: &str) -> Result<(), Error> {
+        let system = self.clone();
+        let folder = subfolder.to_string();
+        self.run_job(JobKind::ManualBackup, Some(subfolder), move || {
+            Self::perform_backup(
+                &system.app_handle,
+                &system.settings,
+                &system.health,
+                &system.queue,
+                &folder,
+                GenerationOrigin::Manual,
+            )
+        })?
+    }
+
+    /// ジョブを取り消す。該当するジョブが無い（既に完了した）場合は false
+    pub fn cancel_job(&self, job_id: u64) -> bool {
+        self.queue.cancel(job_id)
+    }
+
+    /// 現在のキューの状態（待機中・実行中のジョブ）
+    pub fn queue_state(&self) -> QueueState {
+        self.queue.state()
     }
 
-    pub fn trigger_backup(&self, subfolder: &str) {
-        Self::perform_backup(&self.app_handle, &self.settings, subfolder);
+    /// キューのジョブとして実行し、完了まで待って結果を返す。
+    /// リストア・削除などの操作が、同じサブフォルダのバックアップと同時に実行されないようにします。
+    pub fn run_job<T: Send + 'static>(
+        &self,
+        kind: JobKind,
+        subfolder: Option<&str>,
+        task: impl FnOnce() -> T + Send + 'static,
+    ) -> Result<T, Error> {
+        self.queue.run_and_wait(kind, subfolder, task)
     }
 
+    /// サブフォルダのバックアップを作成する。
+    /// 結果は通知と watcher-state で伝えるほか、失敗・取り消しはエラーとして呼び出し側へも返します。
     fn perform_backup(
         app_handle: &AppHandle,
         settings_lock: &Arc<Mutex<AppSettings>>,
+        health: &Arc<Mutex<WatcherHealth>>,
+        queue: &JobQueue,
         subfolder: &str,
-    ) {
+        origin: GenerationOrigin,
+    ) -> Result<(), Error> {
         println!("バックアップを実行中: {}", subfolder);
-        let settings = settings_lock.lock().unwrap();
-        let src_path = Path::new(&settings.repo_save_path).join(subfolder);
 
-        let exe_path = std::env::current_exe().unwrap_or_default();
-        let exe_dir = exe_path.parent().unwrap_or(Path::new("."));
-        let backups_root = exe_dir.join(BACKUPS_DIR_NAME);
+        // ゲームが書き込み中のファイルをコピーしないよう、内容が落ち着くまで待つ
+        // （待機中に監視スレッドや他の操作を止めないよう、設定のロックは保持しない）
+        let write_complete = {
+            let (src_path, filter, max_wait) = {
+                let settings = settings_lock.lock().unwrap();
+                let folder_path = settings.resolve_backups_root().join(subfolder);
+                (
+                    Path::new(&settings.repo_save_path).join(subfolder),
+                    SubfolderMeta::load(&folder_path)
+                        .overrides
+                        .path_filter(&settings),
+                    Duration::from_millis(settings.write_wait_max_ms),
+                )
+            };
+            let include = |path: &str| filter.includes(path);
+            file_stability::wait_until_stable(&src_path, &include, max_wait)
+        };
+        if !write_complete {
+            println!(
+                "{} の書き込み完了を確認できないままバックアップを作成します",
+                subfolder
+            );
+        }
+
+        // ハッシュ・コピー中に設定の取得・保存や一覧の表示が止まらないよう、
+        // 待機後の設定を複製してロックはすぐに解放する
+        let settings = settings_lock.lock().unwrap().clone();
+        let src_path = Path::new(&settings.repo_save_path).join(subfolder);
 
-        let timestamp_str = Local::now().format("%Y%m%d_%H%M%S").to_string();
-        let dest_path = backups_root.join(subfolder).join(&timestamp_str);
+        let backups_root = settings.resolve_backups_root();
 
-        // コピー処理
-        if let Err(e) = Self::copy_dir_recursive(&src_path, &dest_path) {
-            println!("バックアップに失敗しました: {:?}", e);
-            return;
+        let folder_path = backups_root.join(subfolder);
+        let timestamp_str = Generation::new_id(&folder_path);
+        let overrides = SubfolderMeta::load(&folder_path).overrides;
+
+        // ゲームが同じ内容で上書き保存した場合など、直近の世代と内容が同一なら新しい世代は作らない
+        // （同一世代が増えると、世代制限によって本当に必要な履歴が押し出されてしまうため）
+        let filter = overrides.path_filter(&settings);
+        if Self::is_unchanged_since_latest(&src_path, &folder_path, &filter) {
+            println!(
+                "前回から変更がないため、{} のバックアップをスキップしました",
+                subfolder
+            );
+            // 手動の場合は毎回、自動の場合は同じ内容の保存が続いても通知が溢れないよう一定間隔で通知する
+            let notify = origin == GenerationOrigin::Manual
+                || health.lock().unwrap().should_notify_unchanged(subfolder);
+            if notify {
+                Self::send_notification(
+                    app_handle,
+                    "変更なし",
+                    &format!("{} は前回のバックアップから変更がありません", subfolder),
+                );
+            }
+            health.lock().unwrap().record_success(subfolder);
+            Self::emit_watcher_state_with(app_handle, &settings, health);
+            return Ok(());
         }
 
-        // 世代制限の確認
-        Self::enforce_generation_limit(&backups_root.join(subfolder), settings.max_generations);
+        // 設定された保存形式で世代を作成（進捗を通知し、取り消しを受け付ける）
+        let progress = Progress::new(
+            app_handle,
+            queue.current_job(),
+            Operation::Backup,
+            Some(subfolder),
+        );
+        let generation = match Self::create_generation(
+            &settings,
+            &backups_root,
+            subfolder,
+            &timestamp_str,
+            origin,
+            &progress,
+        ) {
+            Ok(generation) => generation,
+            Err(e) if progress::is_cancelled(&e) => {
+                // 取り消しはユーザーの操作のため、失敗としては記録しない
+                println!("{} のバックアップを取り消しました", subfolder);
+                Self::send_notification(
+                    app_handle,
+                    "バックアップ中止",
+                    &format!("{} のバックアップを取り消しました", subfolder),
+                );
+                return Err(Error::Cancelled(format!(
+                    "{} のバックアップを取り消しました",
+                    subfolder
+                )));
+            }
+            Err(e) => {
+                println!("バックアップに失敗しました: {:?}", e);
+                Self::send_notification(
+                    app_handle,
+                    "バックアップ失敗",
+                    &format!("{} のバックアップに失敗しました: {}", subfolder, e),
+                );
+                health
+                    .lock()
+                    .unwrap()
+                    .record_failure(subfolder, e.to_string());
+                Self::emit_watcher_state_with(app_handle, &settings, health);
+                return Err(Error::io(
+                    format!("{} のバックアップに失敗しました", subfolder),
+                    e,
+                ));
+            }
+        };
+        // 期限内に書き込みが落ち着かなかった世代は、不完全な可能性があることを記録する
+        if !write_complete {
+            let mut meta = generation.load_meta();
+            meta.possibly_incomplete = true;
+            if let Err(e) = generation.save_meta(&meta) {
+                println!("メタ情報の保存に失敗しました: {:?}", e);
+            }
+        }
 
         // Emit update
         Self::emit_state(app_handle, &backups_root);
+        health.lock().unwrap().record_success(subfolder);
+        Self::emit_watcher_state_with(app_handle, &settings, health);
+
+        // 古い世代の整理は、続けて実行する別のジョブとして登録する
+        // （連続したバックアップの後に待機中の整理は1回にまとめられる）
+        let app_handle_clone = app_handle.clone();
+        let settings_clone = settings_lock.clone();
+        let folder = subfolder.to_string();
+        queue.enqueue(JobKind::Prune, Some(subfolder), Duration::ZERO, move || {
+            Self::prune_subfolder(&app_handle_clone, &settings_clone, &folder, &timestamp_str);
+        });
 
-        Self::send_notification(
-            app_handle,
-            "バックアップ作成",
-            &format!("{} のバックアップを作成しました", subfolder),
+        if write_complete {
+            Self::send_notification(
+                app_handle,
+                "バックアップ作成",
+                &format!("{} のバックアップを作成しました", subfolder),
+            );
+        } else {
+            Self::send_notification(
+                app_handle,
+                "書き込み未完了の可能性",
+                &format!(
+                    "{} の書き込みが完了しないままバックアップを作成しました。内容が不完全な可能性があります",
+                    subfolder
+                ),
+            );
+        }
+        Ok(())
+    }
+
+    /// 整理方針・ゴミ箱の保管期間・容量制限に従って古い世代を削除する。
+    /// 直前に作成した世代（keep_id）は容量制限による削除の対象外です。
+    fn prune_subfolder(
+        app_handle: &AppHandle,
+        settings_lock: &Arc<Mutex<AppSettings>>,
+        subfolder: &str,
+        keep_id: &str,
+    ) {
+        // 世代の削除や GC の間も設定の取得・保存が止まらないよう、複製してロックを解放する
+        let settings = settings_lock.lock().unwrap().clone();
+        let backups_root = settings.resolve_backups_root();
+        let folder_path = backups_root.join(subfolder);
+
+        // 整理方針（サブフォルダごとの保持件数が設定されていればそれを優先）に従って古い世代を削除
+        Self::apply_retention(
+            &folder_path,
+            &Self::retention_policy_for(&settings, &folder_path),
+            settings.pre_restore_retention_hours,
         );
+        // 常駐中も保管期間を過ぎたゴミ箱の項目が残り続けないよう、バックアップのたびに確認
+        Self::purge_trash(&backups_root, settings.trash_retention_days);
+
+        // 容量制限を超えた分を、固定されていない古い世代から削除
+        let quota_warning = Self::enforce_quota(&settings, &backups_root, subfolder, keep_id);
+
+        Self::emit_state(app_handle, &backups_root);
+        // 容量の警告や削除がある場合は、作成完了の通知の後に表示する（通知は最後の1件のみ表示されるため）
+        if let Some((title, body)) = quota_warning {
+            Self::send_notification(app_handle, title, &body);
+        }
     }
 
-    /// フォルダ構造を維持したまま、中身を再帰的にコピーする
-    fn copy_dir_recursive(src: &Path, dst: &Path) -> std::io::Result<()> {
-        if !dst.exists() {
-            fs::create_dir_all(dst)?;
+    /// 見積もりに従って世代を順に削除し、削除した件数を返す（削除に失敗した時点で中断する）
+    fn remove_planned<'a>(generations: impl Iterator<Item = &'a Generation>) -> usize {
+        let mut removed = 0;
+        for generation in generations {
+            if let Err(e) = generation.remove() {
+                println!(
+                    "容量制限による世代の削除に失敗しました: {} ({:?})",
+                    generation.id, e
+                );
+                break;
+            }
+            removed += 1;
         }
-        for entry in WalkDir::new(src) {
-            let entry = entry?;
-            let rel_path = entry.path().strip_prefix(src).unwrap();
-            let dest_path = dst.join(rel_path);
+        removed
+    }
 
-            if entry.file_type().is_dir() {
-                // ディレクトリなら作成
-                fs::create_dir_all(dest_path)?;
-            } else {
-                // ファイルならコピー（既に存在する場合は上書き）
-                fs::copy(entry.path(), dest_path)?;
+    /// 容量制限を超えている場合、固定されていない古い世代から順に削除する。
+    /// 直前に作成した世代（subfolder の keep_id）は削除しません。
+    /// 削除しても上限を下回れない場合（共有しているオブジェクトが多い、直前の世代だけで上限を超えるなど）は何も削除しません。
+    /// 削除後も上限を超えている、または上限に近づいている場合は、通知するタイトルと本文を返します。
+    fn enforce_quota(
+        settings: &AppSettings,
+        backups_root: &Path,
+        subfolder: &str,
+        keep_id: &str,
+    ) -> Option<(&'static str, String)> {
+        let quota = settings.quota();
+        let retention_hours = settings.pre_restore_retention_hours;
+        let deletable = |g: &Generation| {
+            let meta = g.load_meta();
+            !meta.pinned && !meta.is_protected_pre_restore(retention_hours)
+        };
+
+        let mut pruned = 0;
+        let mut over_limit = None;
+        let mut near_limit = None;
+
+        // 削除で上限を下回れるかを先に見積もり、下回れない場合は削除しない
+        // （他の世代と共有しているオブジェクトは解放されないため、削除しても使用量が減らない場合がある）
+
+        // 1. サブフォルダごとの上限（このサブフォルダ内の古い世代から削除）
+        if let Some(limit) = quota.subfolder_bytes {
+            let folder_path = backups_root.join(subfolder);
+            let mut usage = quota::subfolder_usage(&folder_path);
+            if usage > limit {
+                let generations = Generation::list(&folder_path);
+                let holders: Vec<Footprint> =
+                    generations.iter().map(Footprint::of_generation).collect();
+                let candidates: Vec<usize> = (0..generations.len())
+                    .filter(|&i| generations[i].id != keep_id && deletable(&generations[i]))
+                    .collect();
+                if let Some(count) = quota::plan_removals(usage, limit, &holders, &candidates) {
+                    pruned +=
+                        Self::remove_planned(candidates[..count].iter().map(|&i| &generations[i]));
+                    Self::collect_garbage(backups_root);
+                    usage = quota::subfolder_usage(&folder_path);
+                }
+            }
+            if usage > limit {
+                over_limit = Some((subfolder.to_string(), usage, limit));
+            } else if quota::is_near_limit(usage, limit) {
+                near_limit = Some((subfolder.to_string(), usage, limit));
             }
         }
-        Ok(())
+
+        // 2. 全体の上限（ゴミ箱の古い項目を先に完全削除し、その後は全サブフォルダを通して作成日時が最も古い世代から削除）
+        if let Some(limit) = quota.total_bytes {
+            let mut usage = quota::store_usage(backups_root);
+            if usage > limit {
+                let trash = Trash::new(backups_root);
+                let mut holders = Vec::new();
+                let mut candidates = Vec::new();
+
+                // ゴミ箱の項目（古い順）。項目情報の無いフォルダも、オブジェクトの参照元として数える
+                let entry_dirs = Trash::entry_dirs(backups_root);
+                let mut trash_ids = Vec::new();
+                for entry in trash.list().into_iter().rev() {
+                    if let Some(dir) = entry_dirs.iter().find(|d| d.ends_with(&entry.id)) {
+                        candidates.push(holders.len());
+                        holders.push(Footprint::of_trash_entry(dir));
+                        trash_ids.push(entry.id);
+                    }
+                }
+                for dir in &entry_dirs {
+                    if !trash_ids.iter().any(|id| dir.ends_with(id)) {
+                        holders.push(Footprint::of_trash_entry(dir));
+                    }
+                }
+
+                // 全サブフォルダの世代（削除できるものを作成日時の古い順に候補とし、それ以外は参照元としてのみ数える）
+                let mut generations: Vec<(Option<i64>, Generation)> =
+                    Self::list_backup_subfolders(backups_root)
+                        .iter()
+                        .flat_map(|name| {
+                            Generation::list(&backups_root.join(name))
+                                .into_iter()
+                                .map(move |g| (name == subfolder && g.id == keep_id, g))
+                        })
+                        .map(|(keep, g)| {
+                            let created_at = (!keep && deletable(&g))
+                                .then(|| g.created_at_epoch(&g.load_meta()));
+                            (created_at, g)
+                        })
+                        .collect();
+                generations.sort_by_key(|(created_at, _)| created_at.unwrap_or(i64::MAX));
+                let first_generation = holders.len();
+                for (created_at, generation) in &generations {
+                    if created_at.is_some() {
+                        candidates.push(holders.len());
+                    }
+                    holders.push(Footprint::of_generation(generation));
+                }
+
+                if let Some(count) = quota::plan_removals(usage, limit, &holders, &candidates) {
+                    let planned = &candidates[..count];
+                    let trash_count = planned.iter().filter(|&&i| i < trash_ids.len()).count();
+                    for id in &trash_ids[..trash_count] {
+                        match trash.remove(id) {
+                            Ok(_) => println!("容量制限のためゴミ箱の項目を削除しました: {}", id),
+                            Err(e) => {
+                                println!("ゴミ箱の項目の削除に失敗しました: {} ({:?})", id, e)
+                            }
+                        }
+                    }
+                    pruned += Self::remove_planned(
+                        planned[trash_count..]
+                            .iter()
+                            .map(|&i| &generations[i - first_generation].1),
+                    );
+                    Self::collect_garbage(backups_root);
+                    usage = quota::store_usage(backups_root);
+                }
+            }
+            if usage > limit {
+                over_limit = over_limit.or(Some(("バックアップ全体".to_string(), usage, limit)));
+            } else if quota::is_near_limit(usage, limit) {
+                near_limit = near_limit.or(Some(("バックアップ全体".to_string(), usage, limit)));
+            }
+        }
+
+        let pruned_note = if pruned > 0 {
+            format!("（容量制限のため古い世代を {} 件削除しました）", pruned)
+        } else {
+            String::new()
+        };
+        if pruned > 0 {
+            println!("容量制限のため {} 件の世代を削除しました", pruned);
+        }
+
+        if let Some((target, usage, limit)) = over_limit {
+            // 新しい世代は残したまま、削除できる世代が無いことを知らせる
+            return Some((
+                "容量不足",
+                format!(
+                    "{} の使用量が上限を超えています（{} / {}）。固定を解除するか不要な世代を削除してください{}",
+                    target,
+                    quota::format_mb(usage),
+                    quota::format_mb(limit),
+                    pruned_note
+                ),
+            ));
+        }
+        if let Some((target, usage, limit)) = near_limit {
+            return Some((
+                "容量警告",
+                format!(
+                    "{} の使用量が上限に近づいています（{} / {}）{}",
+                    target,
+                    quota::format_mb(usage),
+                    quota::format_mb(limit),
+                    pruned_note
+                ),
+            ));
+        }
+        if pruned > 0 {
+            return Some((
+                "バックアップ作成",
+                format!("{} のバックアップを作成しました{}", subfolder, pruned_note),
+            ));
+        }
+        None
     }
 
-    fn enforce_generation_limit(backup_folder_path: &Path, limit: usize) {
-        // バックアップフォルダ内のディレクトリ（タイムスタンプ形式の名前）をリストアップ
-        let Ok(entries) = fs::read_dir(backup_folder_path) else {
-            return;
+    /// 現在のセーブデータから、設定された保存形式で新しい世代を作成する。
+    /// 取り消された場合や失敗した場合は、作成途中の世代を削除します。
+    fn create_generation(
+        settings: &AppSettings,
+        backups_root: &Path,
+        subfolder: &str,
+        id: &str,
+        origin: GenerationOrigin,
+        progress: &Progress,
+    ) -> io::Result<Generation> {
+        let src_path = Path::new(&settings.repo_save_path).join(subfolder);
+        let folder_path = backups_root.join(subfolder);
+        // 対象パターンに一致しないファイル・除外したファイルは世代に含めない
+        let filter = SubfolderMeta::load(&folder_path)
+            .overrides
+            .path_filter(settings);
+        let include = |path: &str| filter.includes(path);
+
+        // 同じ識別子の世代が既にある場合、上書きすると既存の履歴が壊れるため作成しない
+        if Generation::find(&folder_path, id).is_some() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("同じIDの世代が既に存在します: {}", id),
+            ));
+        }
+
+        let stored = match settings.storage_format.as_str() {
+            // 1世代を1つの zip ファイルとして保存（書き込み途中のアーカイブは ArchiveStore が削除する）
+            STORAGE_FORMAT_ARCHIVE => {
+                let archive_path = folder_path.join(format!("{}.{}", id, ARCHIVE_EXTENSION));
+                ArchiveStore::create(&src_path, &archive_path, &include, progress)
+                    .map(|manifest| (archive_path, manifest))
+            }
+            // オブジェクトストアへ格納（内容が同じファイルは再利用され、世代にはマニフェストのみ記録される）
+            _ => {
+                let dest_path = folder_path.join(id);
+                match ObjectStore::new(backups_root)
+                    .store_dir(&src_path, &dest_path, &include, progress)
+                {
+                    Ok(manifest) => Ok((dest_path, manifest)),
+                    Err(e) => {
+                        // 途中まで格納したオブジェクトのうち、他の世代から参照されないものも回収する
+                        remove_leftover(&dest_path);
+                        Self::collect_garbage(backups_root);
+                        Err(e)
+                    }
+                }
+            }
         };
+        progress.finish(&stored);
+        let (generation_path, manifest) = stored?;
+
+        let generation = Generation::find(&folder_path, id).ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                format!(
+                    "作成した世代が見つかりません: {}",
+                    generation_path.display()
+                ),
+            )
+        })?;
+        generation.save_meta(&GenerationMeta::new(origin, &manifest))?;
+        Ok(generation)
+    }
 
-        let mut backups: Vec<(PathBuf, String)> = entries
-            .filter_map(|e| e.ok())
-            .filter(|e| e.path().is_dir())
-            .map(|e| (e.path(), e.file_name().to_string_lossy().to_string()))
+    /// ソースフォルダの内容が、最新の世代と（ハッシュ比較で）同一かどうか
+    fn is_unchanged_since_latest(src_path: &Path, folder_path: &Path, filter: &PathFilter) -> bool {
+        let Some(latest) = Generation::list(folder_path).pop() else {
+            return false;
+        };
+        // 対象外のファイルは世代に含まれないため、比較の対象からも外す
+        let include = |path: &str| filter.includes(path);
+        // どちらかが読めない場合は、念のためバックアップを作成する側に倒す
+        match (
+            latest.manifest(),
+            ObjectStore::scan_dir_filtered(src_path, &include),
+        ) {
+            (Ok(latest), Ok(current)) => latest.same_content(&current),
+            _ => false,
+        }
+    }
+
+    /// サブフォルダに適用する整理方針（サブフォルダごとの保持件数が設定されていればそれを優先）。
+    /// 実際の整理とドライランで同じ方針になるよう、両方からこの関数を使用します。
+    fn retention_policy_for(settings: &AppSettings, backup_folder_path: &Path) -> RetentionPolicy {
+        SubfolderMeta::load(backup_folder_path)
+            .overrides
+            .retention_policy(settings)
+    }
+
+    /// 整理方針に従って削除される世代を求める（実際の削除は行わない）
+    fn plan_retention(
+        backup_folder_path: &Path,
+        policy: &RetentionPolicy,
+        pre_restore_retention_hours: u64,
+    ) -> Vec<Generation> {
+        // バックアップフォルダ内の世代を、保存形式を問わず古い順（タイムスタンプの昇順）にリストアップ
+        // 固定された世代と保護期間内のリストア前スナップショットは、削除対象にも件数にも含めない
+        let backups: Vec<(Generation, i64)> = Generation::list(backup_folder_path)
+            .into_iter()
+            .filter_map(|g| {
+                let meta = g.load_meta();
+                if meta.pinned || meta.is_protected_pre_restore(pre_restore_retention_hours) {
+                    return None;
+                }
+                let created_at = g.created_at_epoch(&meta);
+                Some((g, created_at))
+            })
             .collect();
 
-        // 名前（タイムスタンプ）で昇順ソート（古いものが最初に来る）
-        backups.sort_by(|a, b| a.1.cmp(&b.1));
+        let created_at: Vec<i64> = backups.iter().map(|(_, t)| *t).collect();
+        policy
+            .expired(&created_at, Local::now())
+            .into_iter()
+            .map(|index| backups[index].0.clone())
+            .collect()
+    }
+
+    fn apply_retention(
+        backup_folder_path: &Path,
+        policy: &RetentionPolicy,
+        pre_restore_retention_hours: u64,
+    ) {
+        let expired = Self::plan_retention(backup_folder_path, policy, pre_restore_retention_hours);
+        if expired.is_empty() {
+            return;
+        }
+
+        for generation in &expired {
+            // 削除できなかった世代は次回のバックアップ後に改めて整理の対象となる
+            if let Err(e) = generation.remove() {
+                println!("古い世代の削除に失敗しました: {} ({:?})", generation.id, e);
+            }
+        }
+
+        // 削除した世代からしか参照されていなかったオブジェクトを回収
+        if let Some(backups_root) = backup_folder_path.parent() {
+            Self::collect_garbage(backups_root);
+        }
+    }
 
-        // 保持件数を超えている場合、古い順に削除
-        if backups.len() > limit {
-            let to_remove = backups.len() - limit;
-            for i in 0..to_remove {
-                let _ = fs::remove_dir_all(&backups[i].0);
+    /// 指定した設定で整理した場合に削除される世代を列挙する（ドライラン）。
+    /// サブフォルダごとの保持件数も実際の整理と同様に反映します。subfolder を省略すると全サブフォルダが対象です。
+    pub fn preview_retention(
+        settings: &AppSettings,
+        subfolder: Option<&str>,
+    ) -> Vec<RetentionCandidate> {
+        let backups_root = settings.resolve_backups_root();
+        let subfolders: Vec<String> = match subfolder {
+            Some(name) => vec![name.to_string()],
+            None => Self::list_backup_subfolders(&backups_root),
+        };
+
+        subfolders
+            .iter()
+            .flat_map(|name| {
+                let folder_path = backups_root.join(name);
+                Self::plan_retention(
+                    &folder_path,
+                    &Self::retention_policy_for(settings, &folder_path),
+                    settings.pre_restore_retention_hours,
+                )
+                .into_iter()
+                .map(|g| RetentionCandidate {
+                    subfolder: name.clone(),
+                    timestamp: g.id,
+                })
+            })
+            .collect()
+    }
+
+    /// 削除から retention_days 日が経過したゴミ箱の項目を完全に削除する
+    fn purge_trash(backups_root: &Path, retention_days: u64) {
+        match Trash::new(backups_root).purge_older_than(retention_days) {
+            Ok(0) => {}
+            Ok(n) => {
+                println!("保管期間を過ぎたゴミ箱の項目を {} 件削除しました", n);
+                Self::collect_garbage(backups_root);
             }
+            Err(e) => println!("ゴミ箱の整理に失敗しました: {:?}", e),
+        }
+    }
+
+    fn collect_garbage(backups_root: &Path) {
+        match ObjectStore::new(backups_root).collect_garbage() {
+            Ok(0) => {}
+            Ok(n) => println!("未参照のオブジェクトを {} 件削除しました", n),
+            Err(e) => println!("オブジェクトの回収に失敗しました: {:?}", e),
         }
     }
 
@@ -380,35 +1379,20 @@ impl BackupSystem {
                         continue;
                     }
                     let name = entry.file_name().to_string_lossy().to_string();
-
-                    // meta.json を読み込み
-                    let meta_path = entry.path().join("meta.json");
-                    let memo = if meta_path.exists() {
-                        let content = fs::read_to_string(&meta_path).unwrap_or_default();
-                        let json: serde_json::Value =
-                            serde_json::from_str(&content).unwrap_or(serde_json::json!({}));
-                        json["memo"].as_str().unwrap_or("").to_string()
-                    } else {
-                        String::new()
-                    };
-
-                    // バックアップ一覧を取得
-                    let mut backups = Vec::new();
-                    if let Ok(bk_entries) = fs::read_dir(entry.path()) {
-                        for bk in bk_entries.filter_map(|e| e.ok()) {
-                            let fname = bk.file_name().to_string_lossy().to_string();
-                            if fname == "meta.json" {
-                                continue;
-                            }
-                            if bk.path().is_dir() {
-                                backups.push(BackupEntry {
-                                    timestamp: fname.clone(),
-                                    timestamp_raw: 0,
-                                });
-                            }
-                        }
+                    // オブジェクト格納フォルダなど、内部管理用のフォルダはサブフォルダとして扱わない
+                    if name.starts_with('.') {
+                        continue;
                     }
-                    backups.sort_by(|a, b| b.timestamp.cmp(&a.timestamp)); // 新しい順
+
+                    // meta.json を読み込み（メモとサブフォルダごとの設定）
+                    let meta = SubfolderMeta::load(&entry.path());
+
+                    // バックアップ一覧を取得（フォルダ形式・アーカイブ形式の両方）
+                    let mut backups: Vec<BackupEntry> = Generation::list(&entry.path())
+                        .into_iter()
+                        .map(BackupEntry::from_generation)
+                        .collect();
+                    backups.reverse(); // 新しい順
 
                     // ソースフォルダが存在するか確認
                     let source_path = repo_root.join(&name);
@@ -418,9 +1402,10 @@ impl BackupSystem {
                         name.clone(),
                         SubFolderState {
                             name,
-                            memo,
+                            memo: meta.memo,
                             backups,
                             source_exists,
+                            overrides: meta.overrides,
                         },
                     );
                 }
@@ -435,6 +1420,10 @@ impl BackupSystem {
                         continue;
                     }
                     let name = entry.file_name().to_string_lossy().to_string();
+                    // リストア用の作業フォルダなどは一覧に含めない
+                    if name.starts_with('.') {
+                        continue;
+                    }
 
                     if !results.contains_key(&name) {
                         // バックアップがまだ存在しないフォルダもリストに含めることで、
@@ -446,6 +1435,7 @@ impl BackupSystem {
                                 memo: String::new(),
                                 backups: Vec::new(),
                                 source_exists: true,
+                                overrides: SubfolderOverrides::default(),
                             },
                         );
                     }
@@ -458,89 +1448,355 @@ impl BackupSystem {
         list
     }
 
-    pub fn save_memo(backups_root: &Path, subfolder: &str, memo: &str) {
+    /// バックアップルート直下のサブフォルダ名を列挙する（内部管理用の "." で始まるフォルダは除く）
+    fn list_backup_subfolders(backups_root: &Path) -> Vec<String> {
+        let Ok(entries) = fs::read_dir(backups_root) else {
+            return Vec::new();
+        };
+        let mut names: Vec<String> = entries
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_dir())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|name| !name.starts_with('.'))
+            .collect();
+        names.sort();
+        names
+    }
+
+    /// サブフォルダ名として使用できるか確認する（バックアップフォルダの外を指す名前や作業用フォルダ名を拒否する）
+    pub fn validate_subfolder_name(subfolder: &str) -> Result<(), Error> {
+        let valid = !subfolder.trim().is_empty()
+            && !subfolder.starts_with('.')
+            && !subfolder.contains(['/', '\\', ':']);
+        if valid {
+            Ok(())
+        } else {
+            Err(Error::InvalidName(format!(
+                "サブフォルダ名として使用できません: {:?}",
+                subfolder
+            )))
+        }
+    }
+
+    pub fn save_memo(backups_root: &Path, subfolder: &str, memo: &str) -> Result<(), Error> {
+        Self::validate_subfolder_name(subfolder)?;
+        // meta.json の他の項目（サブフォルダごとの設定）は維持したままメモのみ更新
         let folder_path = backups_root.join(subfolder);
-        // バックアップがまだない場合でもメモを保持できるよう、親ディレクトリを作成します。
-        let _ = fs::create_dir_all(&folder_path);
+        let mut meta = SubfolderMeta::load(&folder_path);
+        meta.memo = memo.to_string();
+        meta.save(&folder_path)
+            .map_err(|e| Error::io(format!("{} のメモの保存に失敗しました", subfolder), e))
+    }
 
-        let meta_path = folder_path.join("meta.json");
-        let data = serde_json::json!({ "memo": memo });
-        let _ = fs::write(meta_path, data.to_string());
+    /// サブフォルダ内の世代を探す（見つからない場合は NotFound）
+    fn find_generation(
+        backups_root: &Path,
+        subfolder: &str,
+        timestamp: &str,
+    ) -> Result<Generation, Error> {
+        Self::validate_subfolder_name(subfolder)?;
+        Generation::find(&backups_root.join(subfolder), timestamp).ok_or_else(|| {
+            Error::NotFound(format!(
+                "{} のバックアップ（{}）が見つかりません",
+                subfolder, timestamp
+            ))
+        })
     }
 
+    /// サブフォルダごとの設定を置き換える（すべて未指定にすると全体設定に従う）
+    pub fn set_subfolder_overrides(
+        backups_root: &Path,
+        subfolder: &str,
+        overrides: SubfolderOverrides,
+    ) -> Result<(), Error> {
+        Self::validate_subfolder_name(subfolder)?;
+        let folder_path = backups_root.join(subfolder);
+        let mut meta = SubfolderMeta::load(&folder_path);
+        meta.overrides = overrides.normalized();
+        meta.save(&folder_path)
+            .map_err(|e| Error::io(format!("{} の設定の保存に失敗しました", subfolder), e))
+    }
+
+    /// 世代ごとのメモ・タグを更新する。None の項目は変更しません。
+    pub fn annotate_generation(
+        backups_root: &Path,
+        subfolder: &str,
+        timestamp: &str,
+        memo: Option<String>,
+        tags: Option<Vec<String>>,
+    ) -> Result<(), Error> {
+        let generation = Self::find_generation(backups_root, subfolder, timestamp)?;
+
+        let mut meta = generation.load_meta();
+        if let Some(memo) = memo {
+            meta.memo = memo;
+        }
+        if let Some(tags) = tags {
+            meta.set_tags(tags);
+        }
+        generation
+            .save_meta(&meta)
+            .map_err(|e| Error::io("メモの保存に失敗しました", e))
+    }
+
+    /// 世代を固定、または固定を解除する
+    pub fn set_generation_pinned(
+        backups_root: &Path,
+        subfolder: &str,
+        timestamp: &str,
+        pinned: bool,
+    ) -> Result<(), Error> {
+        let generation = Self::find_generation(backups_root, subfolder, timestamp)?;
+
+        let mut meta = generation.load_meta();
+        meta.pinned = pinned;
+        generation
+            .save_meta(&meta)
+            .map_err(|e| Error::io("固定状態の保存に失敗しました", e))
+    }
+
+    /// 世代をゴミ箱へ移動する。固定された世代は force を指定した場合のみ削除します。
     pub fn delete_backup(
         app_handle: &AppHandle,
         backups_root: &Path,
         subfolder: &str,
         timestamp: &str,
-    ) {
-        let target = backups_root.join(subfolder).join(timestamp);
-        if target.exists() {
-            let _ = fs::remove_dir_all(target);
-            Self::send_notification(
-                app_handle,
-                "バックアップ削除",
-                &format!(
-                    "{} のバックアップ（{}）を削除しました",
-                    subfolder, timestamp
-                ),
-            );
+        force: bool,
+    ) -> Result<(), Error> {
+        let target = Self::find_generation(backups_root, subfolder, timestamp)?;
+        if target.load_meta().pinned && !force {
+            return Err(Error::Locked(format!(
+                "{} のバックアップ（{}）は固定されているため削除できません",
+                subfolder, timestamp
+            )));
         }
+        Trash::new(backups_root)
+            .move_generation(subfolder, &target)
+            .map_err(|e| {
+                Error::io(
+                    format!("{} のバックアップの削除に失敗しました", subfolder),
+                    e,
+                )
+            })?;
+        Self::send_notification(
+            app_handle,
+            "バックアップ削除",
+            &format!(
+                "{} のバックアップ（{}）をゴミ箱へ移動しました",
+                subfolder, timestamp
+            ),
+        );
 
-        // 空（meta以外）になったらフォルダを削除
+        // 空になったらフォルダを削除する。meta.json（メモ・サブフォルダごとの設定）が残っている場合は、
+        // 世代を戻したときに引き継げるようフォルダごと残す（世代の無いフォルダは一覧には表示されない）
         let folder_path = backups_root.join(subfolder);
-        let mut has_backups = false;
-        if let Ok(entries) = fs::read_dir(&folder_path) {
-            for e in entries.filter_map(|x| x.ok()) {
-                if e.file_name().to_string_lossy() != "meta.json" && e.path().is_dir() {
-                    has_backups = true;
-                    break;
-                }
-            }
+        if Generation::list(&folder_path).is_empty() {
+            let _ = fs::remove_dir(&folder_path);
         }
+        Ok(())
+    }
 
-        if !has_backups {
-            let _ = fs::remove_dir_all(folder_path);
+    /// サブフォルダのすべての世代をまとめてゴミ箱へ移動する
+    pub fn delete_subfolder(
+        app_handle: &AppHandle,
+        backups_root: &Path,
+        subfolder: &str,
+    ) -> Result<(), Error> {
+        Self::validate_subfolder_name(subfolder)?;
+        if !backups_root.join(subfolder).exists() {
+            return Err(Error::NotFound(format!(
+                "{} のバックアップが見つかりません",
+                subfolder
+            )));
         }
+        Trash::new(backups_root)
+            .move_subfolder(subfolder)
+            .map_err(|e| {
+                Error::io(
+                    format!("{} のバックアップの削除に失敗しました", subfolder),
+                    e,
+                )
+            })?;
+        Self::send_notification(
+            app_handle,
+            "全バックアップ削除",
+            &format!("{} のすべてのバックアップをゴミ箱へ移動しました", subfolder),
+        );
+        Ok(())
     }
 
-    pub fn delete_subfolder(app_handle: &AppHandle, backups_root: &Path, subfolder: &str) {
-        let folder_path = backups_root.join(subfolder);
-        if folder_path.exists() {
-            let _ = fs::remove_dir_all(folder_path);
+    /// ゴミ箱内の項目を新しい順で返す（保管期間を過ぎた項目は先に完全削除する）
+    pub fn list_trash(backups_root: &Path, retention_days: u64) -> Vec<TrashEntry> {
+        Self::purge_trash(backups_root, retention_days);
+        Trash::new(backups_root).list()
+    }
+
+    /// ゴミ箱の項目を元のサブフォルダへ戻す
+    pub fn restore_from_trash(
+        app_handle: &AppHandle,
+        backups_root: &Path,
+        trash_id: &str,
+    ) -> Result<(), Error> {
+        let entry = Trash::new(backups_root)
+            .restore(trash_id)
+            .map_err(|e| Error::io("ゴミ箱からの復元に失敗しました", e))?;
+
+        let target = match &entry.timestamp {
+            Some(timestamp) => format!("{} のバックアップ（{}）", entry.subfolder, timestamp),
+            None => format!("{} のすべてのバックアップ", entry.subfolder),
+        };
+        Self::send_notification(
+            app_handle,
+            "ゴミ箱から復元",
+            &format!("{} を元に戻しました", target),
+        );
+        Ok(())
+    }
+
+    /// ゴミ箱の項目を完全に削除する。trash_id を省略するとゴミ箱を空にします。
+    pub fn empty_trash(
+        app_handle: &AppHandle,
+        backups_root: &Path,
+        trash_id: Option<&str>,
+    ) -> Result<(), Error> {
+        let trash = Trash::new(backups_root);
+        let result = match trash_id {
+            Some(id) => trash.remove(id).map(|_| 1),
+            None => trash.empty(),
+        };
+        let removed = result.map_err(|e| Error::io("ゴミ箱の削除に失敗しました", e))?;
+
+        // ゴミ箱の世代からしか参照されていなかったオブジェクトを回収
+        Self::collect_garbage(backups_root);
+        if removed > 0 {
             Self::send_notification(
                 app_handle,
-                "全バックアップ削除",
-                &format!("{} のすべてのバックアップを削除しました", subfolder),
+                "ゴミ箱から削除",
+                &format!("{} 件の項目を完全に削除しました", removed),
             );
         }
+        Ok(())
+    }
+
+    /// ゴミ箱の内容をフロントエンドへ通知する
+    pub fn emit_trash_state(app_handle: &AppHandle, backups_root: &Path) {
+        let _ = app_handle.emit("trash-state", Trash::new(backups_root).list());
     }
 
+    /// 世代の内容をマニフェストと突き合わせて検証する。
+    /// subfolder を省略すると全サブフォルダ、timestamp を省略するとサブフォルダ内の全世代が対象です。
+    pub fn verify_backups(
+        app_handle: &AppHandle,
+        backups_root: &Path,
+        subfolder: Option<&str>,
+        timestamp: Option<&str>,
+    ) -> Vec<VerifyReport> {
+        let subfolders: Vec<String> = match subfolder {
+            Some(name) => vec![name.to_string()],
+            None => Self::list_backup_subfolders(backups_root),
+        };
+
+        let mut reports = Vec::new();
+        for name in &subfolders {
+            let folder_path = backups_root.join(name);
+            let generations = match timestamp {
+                Some(ts) => Generation::find(&folder_path, ts).into_iter().collect(),
+                None => Generation::list(&folder_path),
+            };
+            for generation in generations {
+                reports.push(generation.verify(backups_root, name));
+            }
+        }
+
+        let corrupt_count = reports
+            .iter()
+            .filter(|r| r.status == IntegrityStatus::Corrupt)
+            .count();
+        if corrupt_count > 0 {
+            Self::send_notification(
+                app_handle,
+                "バックアップ破損検出",
+                &format!(
+                    "{} 件のバックアップに欠落または破損が見つかりました",
+                    corrupt_count
+                ),
+            );
+        } else {
+            Self::send_notification(
+                app_handle,
+                "検証完了",
+                &format!("{} 件のバックアップに問題はありませんでした", reports.len()),
+            );
+        }
+
+        reports
+    }
+
+    /// 指定した世代をリポジトリ内のサブフォルダへリストアする。
+    /// 失敗した場合も現在のセーブデータは失われず、理由をエラーとして返します。
     pub fn restore_backup(
         &self,
         settings: &AppSettings,
         backups_root: &Path,
         subfolder: &str,
         timestamp: &str,
-    ) {
+    ) -> Result<(), Error> {
+        let generation = Self::find_generation(backups_root, subfolder, timestamp)?;
+
         // リストア中フラグを設定
         {
             let mut lock = self.is_restoring.lock().unwrap();
             *lock = true;
         }
 
-        let src = backups_root.join(subfolder).join(timestamp);
-        let dest = Path::new(&settings.repo_save_path).join(subfolder);
-
-        if src.exists() {
-            if dest.exists() {
-                let _ = fs::remove_dir_all(&dest);
-            }
-            if let Ok(_) = Self::copy_dir_recursive(&src, &dest) {
+        let repo_root = Path::new(&settings.repo_save_path);
+        // スナップショットの作成と展開のそれぞれで進捗を通知する（どちらの途中でも取り消せる）
+        let job = self.queue.current_job();
+        // 現在のセーブデータを退避できた場合のみリストアを実行する
+        let snapshot_progress = Progress::new(
+            &self.app_handle,
+            job.clone(),
+            Operation::Backup,
+            Some(subfolder),
+        );
+        let result =
+            Self::capture_pre_restore(settings, backups_root, subfolder, &snapshot_progress)
+                .and_then(|_| {
+                    let filter = SubfolderMeta::load(&backups_root.join(subfolder))
+                        .overrides
+                        .path_filter(settings);
+                    let progress =
+                        Progress::new(&self.app_handle, job, Operation::Restore, Some(subfolder));
+                    Self::restore_atomically(
+                        &generation,
+                        backups_root,
+                        repo_root,
+                        subfolder,
+                        &filter,
+                        &progress,
+                    )
+                });
+
+        match &result {
+            Ok(_) => Self::send_notification(
+                &self.app_handle,
+                "リストア完了",
+                &format!("{} を {} の時点にリストアしました", subfolder, timestamp),
+            ),
+            Err(e) if progress::is_cancelled(e) => Self::send_notification(
+                &self.app_handle,
+                "リストア中止",
+                &format!(
+                    "{} のリストアを取り消しました。セーブデータは変更されていません",
+                    subfolder
+                ),
+            ),
+            Err(e) => {
+                println!("リストアに失敗しました: {:?}", e);
                 Self::send_notification(
                     &self.app_handle,
-                    "リストア完了",
-                    &format!("{} を {} の時点にリストアしました", subfolder, timestamp),
+                    "リストア失敗",
+                    &format!("{} のリストアに失敗しました: {}", subfolder, e),
                 );
             }
         }
@@ -553,9 +1809,281 @@ impl BackupSystem {
         }
 
         // リストア後に source_exists を更新するため状態をリフレッシュ
-        let exe_path = std::env::current_exe().unwrap_or_default();
-        let backups_root = exe_path.parent().unwrap().join("Backups");
-        Self::emit_state(&self.app_handle, &backups_root);
+        Self::emit_state(&self.app_handle, backups_root);
+
+        result.map_err(|e| Error::io(format!("{} のリストアに失敗しました", subfolder), e))
+    }
+
+    /// リストアで上書きされる現在のセーブデータを、作成元が「リストア前」の世代として保存する。
+    /// この世代は undo_last_restore の復元元となり、保護期間中は世代制限で削除されません。
+    fn capture_pre_restore(
+        settings: &AppSettings,
+        backups_root: &Path,
+        subfolder: &str,
+        progress: &Progress,
+    ) -> io::Result<()> {
+        if !Path::new(&settings.repo_save_path).join(subfolder).exists() {
+            return Ok(());
+        }
+
+        let id = Generation::new_id(&backups_root.join(subfolder));
+        Self::create_generation(
+            settings,
+            backups_root,
+            subfolder,
+            &id,
+            GenerationOrigin::PreRestore,
+            progress,
+        )?;
+        Ok(())
+    }
+
+    /// 直近のリストアを取り消し、リストア直前に自動保存したセーブデータへ戻す。
+    /// 取り消し自体もリストアとして扱われるため、もう一度呼ぶと取り消し前の状態へ戻ります。
+    pub fn undo_last_restore(&self, subfolder: &str) -> Result<(), Error> {
+        Self::validate_subfolder_name(subfolder)?;
+        let settings = self.get_settings();
+        let backups_root = settings.resolve_backups_root();
+
+        let Some(snapshot) = Generation::list(&backups_root.join(subfolder))
+            .into_iter()
+            .rev()
+            .find(|g| g.load_meta().is_pre_restore())
+        else {
+            let message = format!("{} には取り消せるリストアがありません", subfolder);
+            Self::send_notification(&self.app_handle, "リストアの取り消し失敗", &message);
+            return Err(Error::NotFound(message));
+        };
+
+        self.restore_backup(&settings, &backups_root, subfolder, &snapshot.id)
+    }
+
+    /// 世代を隣接するステージングフォルダへ展開・検証してから、リネームで現在のフォルダと入れ替える。
+    /// 入れ替えが完了するまで現在のフォルダは退避フォルダとして保持し、失敗時は元に戻します。
+    fn restore_atomically(
+        generation: &Generation,
+        backups_root: &Path,
+        repo_root: &Path,
+        subfolder: &str,
+        filter: &PathFilter,
+        progress: &Progress,
+    ) -> io::Result<()> {
+        let dest = repo_root.join(subfolder);
+        // "." で始まるフォルダは監視・一覧の対象外のため、作業用フォルダとして使用する
+        let staging = repo_root.join(format!(".{}.restore-staging", subfolder));
+        let previous = repo_root.join(format!(".{}.restore-previous", subfolder));
+
+        // 前回の中断で残った作業用フォルダを片付けてから開始
+        if staging.exists() {
+            fs::remove_dir_all(&staging)?;
+        }
+
+        // 1. ステージングへ展開し、内容が世代のマニフェストと一致することを確認
+        // 取り消された場合もここで中断し、ステージングを削除する（現在のフォルダには手を付けない）
+        let staged = generation
+            .restore_to(backups_root, &staging, progress)
+            .and_then(|_| {
+                let expected = generation.manifest()?;
+                if ObjectStore::scan_dir(&staging)?.same_content(&expected) {
+                    Ok(())
+                } else {
+                    Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        "展開した内容がバックアップと一致しません",
+                    ))
+                }
+            });
+        // 対象外のファイルは世代に含まれないため、現在のフォルダから引き継いで失われないようにする
+        let staged = staged.and_then(|_| Self::carry_over_excluded(&dest, &staging, filter));
+        progress.finish(&staged);
+        if let Err(e) = staged {
+            remove_leftover(&staging);
+            return Err(e);
+        }
+
+        // 2. 現在のフォルダを退避し、ステージングを本来の場所へ移動
+        if previous.exists() {
+            fs::remove_dir_all(&previous)?;
+        }
+        let has_current = dest.exists();
+        if has_current {
+            if let Err(e) = fs::rename(&dest, &previous) {
+                remove_leftover(&staging);
+                return Err(e);
+            }
+        }
+        if let Err(e) = fs::rename(&staging, &dest) {
+            // 入れ替えに失敗した場合は、退避したフォルダを元に戻す
+            if has_current {
+                if let Err(rollback) = fs::rename(&previous, &dest) {
+                    // 元に戻せなかった場合、現在のセーブデータは退避フォルダにのみ残っている。
+                    // ステージングも削除せず、どちらも手動で取り出せるようにしてその場所を伝える
+                    println!("退避フォルダを元に戻せませんでした: {:?}", rollback);
+                    return Err(io::Error::new(
+                        rollback.kind(),
+                        format!(
+                            "{}。現在のセーブデータは {} に、リストアする内容は {} に残っています",
+                            e,
+                            previous.display(),
+                            staging.display()
+                        ),
+                    ));
+                }
+            }
+            remove_leftover(&staging);
+            return Err(e);
+        }
+
+        // 3. 入れ替え完了後に退避フォルダを削除（失敗してもリストア自体は成功）
+        if has_current {
+            if let Err(e) = fs::remove_dir_all(&previous) {
+                println!("退避フォルダの削除に失敗しました: {:?}", e);
+            }
+        }
+        Ok(())
+    }
+
+    /// current 内の対象外のファイルのうち、staging に存在しないものを同じ相対位置へコピーする
+    fn carry_over_excluded(current: &Path, staging: &Path, filter: &PathFilter) -> io::Result<()> {
+        if !current.exists() {
+            return Ok(());
+        }
+        for entry in WalkDir::new(current) {
+            let entry = entry?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let rel_path = entry.path().strip_prefix(current).unwrap();
+            if filter.includes(&to_manifest_path(rel_path)) {
+                continue;
+            }
+            let dest_path = staging.join(rel_path);
+            if dest_path.exists() {
+                continue;
+            }
+            if let Some(parent) = dest_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(entry.path(), dest_path)?;
+        }
+        Ok(())
+    }
+
+    /// バックアップストア全体を new_root へ移動し、設定の backups_root を更新する。
+    /// 途中で失敗した場合（取り消した場合を含む）は移行先の途中コピーを削除し、元のストアと設定をそのまま残します。
+    pub fn migrate_backups_root(&self, new_root: &Path) -> Result<(), Error> {
+        // 他のバックアップ処理とはジョブキューで直列化されるため、設定のロックは移動中には保持しない
+        // （保持すると、移動が終わるまで設定の取得・保存や監視の処理がすべて止まってしまう）
+        let old_root = self.settings.lock().unwrap().resolve_backups_root();
+
+        if new_root == old_root {
+            return Ok(());
+        }
+        if new_root.starts_with(&old_root) || old_root.starts_with(new_root) {
+            return Err(Error::InvalidArgument(
+                "移行元と移行先のフォルダが入れ子になっています".to_string(),
+            ));
+        }
+
+        let progress = Progress::new(
+            &self.app_handle,
+            self.queue.current_job(),
+            Operation::Migration,
+            None,
+        );
+        let moved = Self::move_store(&old_root, new_root, &progress);
+        progress.finish(&moved);
+        if let Err(e) = moved {
+            if progress::is_cancelled(&e) {
+                println!("バックアップフォルダの移行を取り消しました");
+                return Err(Error::Cancelled(
+                    "バックアップフォルダの移行を取り消しました".to_string(),
+                ));
+            }
+            println!("バックアップフォルダの移行に失敗しました: {:?}", e);
+            return Err(Error::io("バックアップフォルダの移行に失敗しました", e));
+        }
+
+        // ストアは移動済みのため、設定ファイルへ保存できなかった場合も実行中の設定は新しい場所を指す
+        // 移動中に変更された他の設定を上書きしないよう、ロックを取り直して backups_root のみ更新する
+        let mut settings = self.settings.lock().unwrap();
+        settings.backups_root = new_root.to_string_lossy().to_string();
+        let saved = SettingsManager::new().save(&settings);
+        let new_settings = settings.clone();
+        drop(settings);
+
+        let _ = self.app_handle.emit("settings-state", new_settings);
+        Self::emit_state(&self.app_handle, new_root);
+        saved?;
+        Self::send_notification(
+            &self.app_handle,
+            "移行完了",
+            &format!(
+                "バックアップフォルダを {} へ移動しました",
+                new_root.display()
+            ),
+        );
+        Ok(())
+    }
+
+    fn move_store(old_root: &Path, new_root: &Path, progress: &Progress) -> io::Result<()> {
+        // 移行先は存在しないか、空のフォルダであること（既存データとの混在を防ぐ）
+        let existed = new_root.exists();
+        if existed {
+            if fs::read_dir(new_root)?.next().is_some() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    "移行先のフォルダが空ではありません",
+                ));
+            }
+            // 切り替えはリネームで行うため、空のフォルダは一旦削除する
+            fs::remove_dir(new_root)?;
+        }
+
+        let moved = Self::move_store_into(old_root, new_root, progress);
+        // 失敗した場合は、ユーザーが用意していた空のフォルダを元に戻す
+        if moved.is_err() && existed && !new_root.exists() {
+            if let Err(e) = fs::create_dir(new_root) {
+                println!("移行先のフォルダを元に戻せませんでした: {:?}", e);
+            }
+        }
+        moved
+    }
+
+    /// old_root を、存在しない new_root へ移動する
+    fn move_store_into(old_root: &Path, new_root: &Path, progress: &Progress) -> io::Result<()> {
+        if let Some(parent) = new_root.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if !old_root.exists() {
+            return fs::create_dir_all(new_root);
+        }
+
+        // 同一ドライブ内であればリネームのみで完了する（アトミック）
+        if fs::rename(old_root, new_root).is_ok() {
+            return Ok(());
+        }
+
+        // 別ドライブの場合は、移行先の隣のステージングフォルダへコピーしてから切り替える
+        let staging = new_root.with_file_name(format!(
+            ".{}.migrating",
+            new_root.file_name().unwrap_or_default().to_string_lossy()
+        ));
+        remove_leftover(&staging);
+
+        // 取り消された場合もロールバック: 途中コピーを破棄し、元のストアには手を付けない
+        let copied = copy_dir_recursive(old_root, &staging, progress)
+            .and_then(|_| fs::rename(&staging, new_root));
+        if let Err(e) = copied {
+            remove_leftover(&staging);
+            return Err(e);
+        }
+
+        // 新しい場所への切り替えは完了しているため、旧ストアの削除失敗は移行の失敗とはしない
+        if let Err(e) = fs::remove_dir_all(old_root) {
+            println!("旧バックアップフォルダの削除に失敗しました: {:?}", e);
+        }
+        Ok(())
     }
 
     pub fn send_notification(app: &AppHandle, title: &str, body: &str) {
@@ -563,7 +2091,14 @@ impl BackupSystem {
         // 通知ウィンドウにイベントを送信
         // ペイロード: { title, body, type } - 現在は簡略化
         // 通知の種類（アイコン/色）を、タイトルに含まれるキーワードに基づいて判定
-        let type_str = if title.contains("バックアップ") {
+        let type_str = if title.contains("失敗") {
+            "error"
+        } else if title.contains("変更なし") {
+            "unchanged"
+        } else if title.contains("容量") || title.contains("未完了") || title.contains("監視")
+        {
+            "warning"
+        } else if title.contains("バックアップ") {
             "backup"
         } else if title.contains("リストア") {
             "restore"
@@ -583,3 +2118,22 @@ impl BackupSystem {
         let _ = app.emit("show-notification", payload);
     }
 }
+
+/// 中断した処理の作業用フォルダなどを削除する。
+/// 削除できなくても元の処理の結果は変えず（次回の処理の開始時に改めて削除される）、ログに残します。
+fn remove_leftover(path: &Path) {
+    match fs::remove_dir_all(path) {
+        Ok(_) => {}
+        Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+        Err(e) => println!(
+            "作業用フォルダの削除に失敗しました: {} ({:?})",
+            path.display(),
+            e
+        ),
+    }
+}
+
+/// path 自身、または存在する最も近い親フォルダ
+fn nearest_existing_dir(path: &Path) -> Option<PathBuf> {
+    path.ancestors().find(|p| p.is_dir()).map(Path::to_path_buf)
+}
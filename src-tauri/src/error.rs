@@ -0,0 +1,96 @@
+use serde::ser::SerializeStruct;
+use serde::{Serialize, Serializer};
+use std::fmt;
+use std::io;
+
+/// コマンドがフロントエンドへ返すエラー。
+/// フロントエンドへは { kind, message } の形で渡され、kind によって種類ごとの表示を切り替えられます。
+#[derive(Debug)]
+pub enum Error {
+    /// ファイルの読み書きに失敗した（context は行おうとしていた操作）
+    Io { context: String, source: io::Error },
+    /// 指定したサブフォルダ・世代・ゴミ箱の項目・フォルダが見つからない
+    NotFound(String),
+    /// サブフォルダ名・世代ID・パスとして使用できない
+    InvalidName(String),
+    /// 固定されている、または他のプログラムが使用中のため操作できない
+    Locked(String),
+    /// バックアップの内容が記録と一致しない、または読み込めない
+    CorruptBackup(String),
+    /// 同じ世代・項目が既に存在する
+    AlreadyExists(String),
+    /// 指定した値が不正
+    InvalidArgument(String),
+    /// ユーザーの操作により処理を取り消した
+    Cancelled(String),
+    /// ジョブの異常終了など、想定外の失敗
+    Internal(String),
+}
+
+impl Error {
+    /// io::Error を、行おうとしていた操作（context）を添えて種類ごとに分類する
+    pub fn io(context: impl Into<String>, source: io::Error) -> Self {
+        let context = context.into();
+        let message = || format!("{}: {}", context, source);
+        match source.kind() {
+            io::ErrorKind::Interrupted => Self::Cancelled(message()),
+            io::ErrorKind::NotFound => Self::NotFound(message()),
+            io::ErrorKind::AlreadyExists => Self::AlreadyExists(message()),
+            io::ErrorKind::InvalidData => Self::CorruptBackup(message()),
+            // ゲームがセーブデータを開いたままの場合など（Windows では共有違反もここに含まれる）
+            io::ErrorKind::PermissionDenied | io::ErrorKind::ResourceBusy => {
+                Self::Locked(message())
+            }
+            _ => Self::Io { context, source },
+        }
+    }
+
+    /// フロントエンドで種類を判別するための識別子
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::Io { .. } => "io",
+            Self::NotFound(_) => "not_found",
+            Self::InvalidName(_) => "invalid_name",
+            Self::Locked(_) => "locked",
+            Self::CorruptBackup(_) => "corrupt_backup",
+            Self::AlreadyExists(_) => "already_exists",
+            Self::InvalidArgument(_) => "invalid_argument",
+            Self::Cancelled(_) => "cancelled",
+            Self::Internal(_) => "internal",
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Io { context, source } => write!(f, "{}: {}", context, source),
+            Self::NotFound(message)
+            | Self::InvalidName(message)
+            | Self::Locked(message)
+            | Self::CorruptBackup(message)
+            | Self::AlreadyExists(message)
+            | Self::InvalidArgument(message)
+            | Self::Cancelled(message)
+            | Self::Internal(message) => f.write_str(message),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
+
+impl Serialize for Error {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("Error", 2)?;
+        state.serialize_field("kind", self.kind())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
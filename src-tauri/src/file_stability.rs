@@ -0,0 +1,84 @@
+use crate::object_store::{to_manifest_path, FileFilter};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
+use walkdir::WalkDir;
+
+/// 書き込み完了の確認でファイルの状態を見比べる間隔
+const STABILITY_CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// ファイルごとのパス・サイズ・更新日時
+type Snapshot = Vec<(PathBuf, u64, Option<SystemTime>)>;
+
+/// src 配下のファイル（include が true のもの）の状態を記録する
+fn snapshot(src: &Path, include: FileFilter) -> io::Result<Snapshot> {
+    let mut files = Vec::new();
+    for entry in WalkDir::new(src).sort_by_file_name() {
+        let entry = entry.map_err(io::Error::from)?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        if !include(&to_manifest_path(entry.path().strip_prefix(src).unwrap())) {
+            continue;
+        }
+        let metadata = entry.metadata().map_err(io::Error::from)?;
+        files.push((
+            entry.path().to_path_buf(),
+            metadata.len(),
+            metadata.modified().ok(),
+        ));
+    }
+    Ok(files)
+}
+
+/// すべてのファイルを読み込み用に開けるかどうか（ゲームが排他的に書き込み中の場合は開けない）
+fn all_readable(snapshot: &Snapshot) -> bool {
+    snapshot
+        .iter()
+        .all(|(path, _, _)| fs::File::open(path).is_ok())
+}
+
+/// src 配下のファイルのサイズと更新日時が連続した確認で変化せず、すべて読み込み可能になるまで待つ。
+/// max_wait 以内に落ち着いた場合は true、期限を過ぎた場合は false を返します（max_wait が 0 の場合は待たずに true）。
+/// src が存在しない場合や読み取れない場合は、書き込み中とはみなさずに待機を終えて true を返します
+/// （原因のエラーは、続くバックアップの処理で報告される）。
+pub fn wait_until_stable(src: &Path, include: FileFilter, max_wait: Duration) -> bool {
+    if max_wait.is_zero() || !src.exists() {
+        return true;
+    }
+
+    let deadline = Instant::now() + max_wait;
+    let mut previous = match snapshot(src, include) {
+        Ok(files) => Some(files),
+        Err(e) if is_replaced_during_scan(src, &e) => None,
+        Err(_) => return true,
+    };
+    loop {
+        let now = Instant::now();
+        if now >= deadline {
+            return false;
+        }
+        thread::sleep(STABILITY_CHECK_INTERVAL.min(deadline - now));
+
+        let current = match snapshot(src, include) {
+            Ok(files) => files,
+            // 走査中にファイルが置き換えられた場合は、次の確認まで待つ
+            Err(e) if is_replaced_during_scan(src, &e) => {
+                previous = None;
+                continue;
+            }
+            Err(_) => return true,
+        };
+        if previous.as_ref() == Some(&current) && all_readable(&current) {
+            return true;
+        }
+        previous = Some(current);
+    }
+}
+
+/// 走査の失敗が、保存時のファイルの置き換え（一時ファイルからのリネームなど）によるものかどうか
+fn is_replaced_during_scan(src: &Path, error: &io::Error) -> bool {
+    error.kind() == io::ErrorKind::NotFound && src.exists()
+}
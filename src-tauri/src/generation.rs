@@ -0,0 +1,443 @@
+use crate::archive_store::{ArchiveStore, ARCHIVE_EXTENSION};
+use crate::object_store::{list_files, IntegrityIssues, Manifest, ObjectStore};
+use crate::progress::Progress;
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use walkdir::WalkDir;
+
+/// 世代ごとのメタ情報ファイルの接尾辞（例: "20231024_153000_123.gen.json"）
+const GENERATION_META_SUFFIX: &str = ".gen.json";
+/// 世代IDの形式（ミリ秒まで）。同じミリ秒に作成した世代には "_001" のような連番が付きます
+const GENERATION_ID_FORMAT: &str = "%Y%m%d_%H%M%S_%3f";
+/// 秒単位だった旧形式の世代IDの形式。新形式の世代IDも先頭はこの形式と一致します
+const LEGACY_GENERATION_ID_FORMAT: &str = "%Y%m%d_%H%M%S";
+/// 世代IDのうち、秒単位のタイムスタンプ部分の長さ
+const GENERATION_ID_SECONDS_LEN: usize = 15;
+
+// 直前に発行した世代IDの基準部分と連番。
+// 手動バックアップと自動バックアップが同じミリ秒に重なっても、同じIDを発行しないようにする
+static LAST_ISSUED_ID: Mutex<(String, u32)> = Mutex::new((String::new(), 0));
+
+/// 世代の整合性チェックの状態
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum IntegrityStatus {
+    /// まだ検証していない（またはマニフェストを持たない旧形式のため検証できない）
+    #[default]
+    Unverified,
+    /// マニフェストと内容が一致した
+    Verified,
+    /// 欠落・余分・破損のいずれかが見つかった
+    Corrupt,
+}
+
+/// 世代が作成されたきっかけ
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum GenerationOrigin {
+    /// フォルダ監視による自動バックアップ
+    Auto,
+    /// ユーザーが「今すぐバックアップ」を実行した
+    Manual,
+    /// リストア直前のセーブデータを自動保存した
+    PreRestore,
+    /// 外部から取り込んだ
+    Imported,
+}
+
+/// 世代ごとに保持するメタ情報（世代と同じフォルダに "<世代ID>.gen.json" として保存）
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct GenerationMeta {
+    #[serde(default)]
+    pub integrity: IntegrityStatus,
+    /// 最後に整合性チェックを行った日時（RFC 3339）
+    #[serde(default)]
+    pub verified_at: Option<String>,
+    /// 世代が作成されたきっかけ（メタ情報の記録開始前に作成された世代では None）
+    #[serde(default)]
+    pub origin: Option<GenerationOrigin>,
+    /// 世代を作成した日時（RFC 3339）
+    #[serde(default)]
+    pub created_at: Option<String>,
+    /// 世代に含まれるファイル数
+    #[serde(default)]
+    pub file_count: Option<usize>,
+    /// 世代に含まれるファイルの合計サイズ（バイト、圧縮前）
+    #[serde(default)]
+    pub total_size: Option<u64>,
+    /// ユーザーがこの時点に付けたメモ（例: "ボス戦の前"）
+    #[serde(default)]
+    pub memo: String,
+    /// ユーザーが付けた自由入力のタグ
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// 固定された世代は世代制限による自動削除の対象外となり、保持件数にも数えない
+    #[serde(default)]
+    pub pinned: bool,
+    /// 書き込み完了を確認できないまま作成した（ゲームが書き込み中だった可能性がある）世代
+    #[serde(default)]
+    pub possibly_incomplete: bool,
+}
+
+impl GenerationMeta {
+    /// 新しく作成した世代のメタ情報を、マニフェストの内容から作成する
+    pub fn new(origin: GenerationOrigin, manifest: &Manifest) -> Self {
+        Self {
+            origin: Some(origin),
+            created_at: Some(Local::now().to_rfc3339()),
+            file_count: Some(manifest.files.len()),
+            total_size: Some(manifest.files.iter().map(|f| f.size).sum()),
+            ..Default::default()
+        }
+    }
+
+    /// タグを前後の空白を除いて設定する（空のタグと重複は取り除き、入力順を維持）
+    pub fn set_tags(&mut self, tags: Vec<String>) {
+        self.tags.clear();
+        for tag in tags {
+            let tag = tag.trim().to_string();
+            if !tag.is_empty() && !self.tags.contains(&tag) {
+                self.tags.push(tag);
+            }
+        }
+    }
+
+    pub fn is_pre_restore(&self) -> bool {
+        self.origin == Some(GenerationOrigin::PreRestore)
+    }
+
+    /// リストア前スナップショットのうち、作成から保護期間内のものかどうか
+    pub fn is_protected_pre_restore(&self, retention_hours: u64) -> bool {
+        if !self.is_pre_restore() {
+            return false;
+        }
+        let Some(created_at) = self
+            .created_at
+            .as_deref()
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        else {
+            return false;
+        };
+        let age = Local::now().signed_duration_since(created_at);
+        age < chrono::Duration::hours(retention_hours as i64)
+    }
+}
+
+/// 1世代分の整合性チェックの結果
+#[derive(Serialize, Clone, Debug)]
+pub struct VerifyReport {
+    pub subfolder: String,
+    pub timestamp: String,
+    pub status: IntegrityStatus,
+    #[serde(flatten)]
+    pub issues: IntegrityIssues,
+    /// マニフェストが読めないなど、ファイル単位の比較ができなかった場合の理由
+    pub error: Option<String>,
+}
+
+/// 世代の保存形式
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum GenerationLayout {
+    /// manifest.json のみを持ち、実体はオブジェクトストアにある世代フォルダ
+    Objects,
+    /// 1つの zip ファイルにまとめられた世代
+    Archive,
+    /// セーブデータをフォルダごとコピーした旧形式の世代フォルダ
+    Directory,
+}
+
+/// サブフォルダ配下の1世代分のバックアップ
+#[derive(Clone, Debug)]
+pub struct Generation {
+    /// 世代の識別子（"20231024_153000_123" のようなミリ秒までのタイムスタンプ。
+    /// 旧形式の世代は秒単位。アーカイブの場合は拡張子を除いたファイル名）
+    pub id: String,
+    /// 世代フォルダ、またはアーカイブファイルのパス
+    pub path: PathBuf,
+    pub layout: GenerationLayout,
+}
+
+impl Generation {
+    /// 指定したパスが世代であれば、その形式を判定して返す
+    fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_string_lossy().to_string();
+        // 一時フォルダなど、"." で始まるものは世代として扱わない
+        if name.starts_with('.') {
+            return None;
+        }
+
+        if path.is_dir() {
+            let layout = if ObjectStore::is_generation(path) {
+                GenerationLayout::Objects
+            } else {
+                GenerationLayout::Directory
+            };
+            Some(Self {
+                id: name,
+                path: path.to_path_buf(),
+                layout,
+            })
+        } else if ArchiveStore::is_archive(path) {
+            Some(Self {
+                id: path.file_stem()?.to_string_lossy().to_string(),
+                path: path.to_path_buf(),
+                layout: GenerationLayout::Archive,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// サブフォルダ配下の全世代を、古い順（識別子の昇順）で返す
+    pub fn list(folder_path: &Path) -> Vec<Self> {
+        let Ok(entries) = fs::read_dir(folder_path) else {
+            return Vec::new();
+        };
+        let mut generations: Vec<Self> = entries
+            .filter_map(|e| e.ok())
+            .filter_map(|e| Self::from_path(&e.path()))
+            .collect();
+        generations.sort_by(|a, b| a.id.cmp(&b.id));
+        generations
+    }
+
+    /// サブフォルダ内で重複しない新しい世代IDを発行する。
+    /// 識別子の昇順が作成順となるよう、連番は桁数を揃えて付けます。
+    pub fn new_id(folder_path: &Path) -> String {
+        let mut last = LAST_ISSUED_ID.lock().unwrap();
+        let base = Local::now().format(GENERATION_ID_FORMAT).to_string();
+        let mut seq = if last.0 == base { last.1 + 1 } else { 0 };
+        loop {
+            let id = if seq == 0 {
+                base.clone()
+            } else {
+                format!("{}_{:03}", base, seq)
+            };
+            if Self::find(folder_path, &id).is_none() {
+                *last = (base, seq);
+                return id;
+            }
+            seq += 1;
+        }
+    }
+
+    /// 識別子に一致する世代を、保存形式を問わず検索する
+    pub fn find(folder_path: &Path, id: &str) -> Option<Self> {
+        // サブフォルダの外や内部管理用のファイルを指す識別子は受け付けない
+        if id.is_empty() || id.starts_with('.') || id.contains(['/', '\\']) {
+            return None;
+        }
+        let dir = folder_path.join(id);
+        if dir.is_dir() {
+            return Self::from_path(&dir);
+        }
+        Self::from_path(&folder_path.join(format!("{}.{}", id, ARCHIVE_EXTENSION)))
+    }
+
+    /// 世代の内容を dest へ展開する（取り消された場合は途中で中断し、dest の後片付けは呼び出し側で行う）
+    pub fn restore_to(
+        &self,
+        backups_root: &Path,
+        dest: &Path,
+        progress: &Progress,
+    ) -> io::Result<()> {
+        match self.layout {
+            GenerationLayout::Objects => {
+                ObjectStore::new(backups_root).restore_dir(&self.path, dest, progress)
+            }
+            GenerationLayout::Archive => ArchiveStore::extract(&self.path, dest, progress),
+            GenerationLayout::Directory => copy_dir_recursive(&self.path, dest, progress),
+        }
+    }
+
+    /// 世代に含まれるファイル構成を取得する。
+    /// マニフェストを持たない旧形式の世代は、フォルダの中身を直接ハッシュして求めます。
+    pub fn manifest(&self) -> io::Result<Manifest> {
+        match self.layout {
+            GenerationLayout::Objects => ObjectStore::read_manifest(&self.path),
+            GenerationLayout::Archive => ArchiveStore::read_manifest(&self.path),
+            GenerationLayout::Directory => ObjectStore::scan_dir(&self.path),
+        }
+    }
+
+    /// 世代の作成日時（UNIX 秒）。
+    /// メタ情報に記録が無い旧形式の世代は、識別子の先頭のタイムスタンプ（秒単位）から求めます。
+    pub fn created_at_epoch(&self, meta: &GenerationMeta) -> i64 {
+        if let Some(created_at) = meta
+            .created_at
+            .as_deref()
+            .and_then(|s| DateTime::parse_from_rfc3339(s).ok())
+        {
+            return created_at.timestamp();
+        }
+        self.id
+            .get(..GENERATION_ID_SECONDS_LEN)
+            .and_then(|s| NaiveDateTime::parse_from_str(s, LEGACY_GENERATION_ID_FORMAT).ok())
+            .and_then(|naive| Local.from_local_datetime(&naive).earliest())
+            .map(|dt| dt.timestamp())
+            .unwrap_or(0)
+    }
+
+    /// 世代に含まれるファイル数と合計サイズを求める。
+    /// 旧形式の世代はハッシュ計算を行わず、ファイルサイズのみを集計します。
+    pub fn content_stats(&self) -> io::Result<(usize, u64)> {
+        match self.layout {
+            GenerationLayout::Directory => {
+                let mut count = 0;
+                let mut size = 0;
+                for entry in WalkDir::new(&self.path) {
+                    let entry = entry.map_err(io::Error::from)?;
+                    if entry.file_type().is_file() {
+                        count += 1;
+                        size += entry.metadata().map_err(io::Error::from)?.len();
+                    }
+                }
+                Ok((count, size))
+            }
+            _ => {
+                let manifest = self.manifest()?;
+                Ok((
+                    manifest.files.len(),
+                    manifest.files.iter().map(|f| f.size).sum(),
+                ))
+            }
+        }
+    }
+
+    /// 世代を削除する（オブジェクトの回収は呼び出し側で行う）
+    pub fn remove(&self) -> io::Result<()> {
+        match self.layout {
+            GenerationLayout::Archive => fs::remove_file(&self.path)?,
+            _ => fs::remove_dir_all(&self.path)?,
+        }
+        let meta_path = self.meta_path();
+        if meta_path.exists() {
+            fs::remove_file(meta_path)?;
+        }
+        Ok(())
+    }
+
+    /// 世代をメタ情報ごと別のフォルダへ移動し、移動後の世代を返す
+    pub fn move_to(&self, dest_folder: &Path) -> io::Result<Self> {
+        fs::create_dir_all(dest_folder)?;
+        let file_name = self
+            .path
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "世代のパスが不正です"))?;
+        let moved = Self {
+            id: self.id.clone(),
+            path: dest_folder.join(file_name),
+            layout: self.layout,
+        };
+
+        // メタ情報を先に移動し、世代の移動に失敗した場合は元に戻す
+        // （世代を先に移動すると、メタ情報の移動に失敗した際に呼び出し側の後始末で世代ごと消えてしまう）
+        let meta_path = self.meta_path();
+        let has_meta = meta_path.exists();
+        if has_meta {
+            fs::rename(&meta_path, moved.meta_path())?;
+        }
+        if let Err(e) = fs::rename(&self.path, &moved.path) {
+            if has_meta {
+                if let Err(e) = fs::rename(moved.meta_path(), &meta_path) {
+                    println!("世代のメタ情報を元に戻せませんでした: {:?}", e);
+                }
+            }
+            return Err(e);
+        }
+        Ok(moved)
+    }
+
+    fn meta_path(&self) -> PathBuf {
+        let folder_path = self.path.parent().unwrap_or(Path::new("."));
+        folder_path.join(format!("{}{}", self.id, GENERATION_META_SUFFIX))
+    }
+
+    /// 世代のメタ情報を読み込む（存在しない・壊れている場合は既定値）
+    pub fn load_meta(&self) -> GenerationMeta {
+        fs::read_to_string(self.meta_path())
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save_meta(&self, meta: &GenerationMeta) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(meta)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(self.meta_path(), json)
+    }
+
+    /// マニフェストに従って世代の内容を再ハッシュし、結果をメタ情報に記録する
+    pub fn verify(&self, backups_root: &Path, subfolder: &str) -> VerifyReport {
+        let result = match self.layout {
+            GenerationLayout::Objects => ObjectStore::new(backups_root).verify_dir(&self.path),
+            GenerationLayout::Archive => ArchiveStore::verify(&self.path),
+            GenerationLayout::Directory => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "旧形式の世代のためマニフェストがありません",
+            )),
+        };
+
+        let (status, issues, error) = match result {
+            Ok(issues) if issues.is_empty() => (IntegrityStatus::Verified, issues, None),
+            Ok(issues) => (IntegrityStatus::Corrupt, issues, None),
+            // マニフェストが無い旧形式は検証不能として扱い、破損とはみなさない
+            Err(e) if self.layout == GenerationLayout::Directory => (
+                IntegrityStatus::Unverified,
+                IntegrityIssues::default(),
+                Some(e.to_string()),
+            ),
+            Err(e) => (
+                IntegrityStatus::Corrupt,
+                IntegrityIssues::default(),
+                Some(e.to_string()),
+            ),
+        };
+
+        let mut meta = self.load_meta();
+        meta.integrity = status;
+        meta.verified_at = Some(Local::now().to_rfc3339());
+        if let Err(e) = self.save_meta(&meta) {
+            println!("検証結果の保存に失敗しました: {:?}", e);
+        }
+
+        VerifyReport {
+            subfolder: subfolder.to_string(),
+            timestamp: self.id.clone(),
+            status,
+            issues,
+            error,
+        }
+    }
+}
+
+/// フォルダ構造を維持したまま、中身を再帰的にコピーする
+/// （オブジェクトストア導入前の、フォルダ丸ごとコピー形式の世代のリストアに使用）
+pub fn copy_dir_recursive(src: &Path, dst: &Path, progress: &Progress) -> io::Result<()> {
+    if !dst.exists() {
+        fs::create_dir_all(dst)?;
+    }
+    let files = list_files(src, &|_| true)?;
+    progress.start(files.len() as u64, files.iter().map(|file| file.size).sum());
+
+    for entry in WalkDir::new(src) {
+        progress.check()?;
+        let entry = entry?;
+        let rel_path = entry.path().strip_prefix(src).unwrap();
+        let dest_path = dst.join(rel_path);
+
+        if entry.file_type().is_dir() {
+            // ディレクトリなら作成
+            fs::create_dir_all(dest_path)?;
+        } else {
+            // ファイルならコピー（既に存在する場合は上書き）
+            let size = fs::copy(entry.path(), dest_path)?;
+            progress.advance(size);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,307 @@
+use crate::error::Error;
+use crate::progress::CancelToken;
+use chrono::Local;
+use serde::Serialize;
+use std::cell::Cell;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::mpsc;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// キューに登録する処理の種類
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobKind {
+    /// 変更検知による自動バックアップ
+    AutoBackup,
+    /// 「今すぐバックアップ」による手動バックアップ
+    ManualBackup,
+    /// 世代からのリストア（リストアの取り消しを含む）
+    Restore,
+    /// 世代・サブフォルダの削除、ゴミ箱の復元・完全削除
+    Delete,
+    /// バックアップ後の整理方針・容量制限による古い世代の削除
+    Prune,
+    /// バックアップの保存先の移行
+    Migration,
+    /// 世代の固定・メモ・タグ、サブフォルダのメモ・設定（meta.json）の更新
+    Annotate,
+    /// 世代の整合性の検証
+    Verify,
+}
+
+/// 待機中・実行中のジョブ
+#[derive(Serialize, Clone, Debug)]
+pub struct JobInfo {
+    pub id: u64,
+    pub kind: JobKind,
+    /// 対象のサブフォルダ名。ゴミ箱全体など、サブフォルダに属さない処理は None
+    pub subfolder: Option<String>,
+    /// キューに登録した日時（RFC 3339）
+    pub queued_at: String,
+}
+
+/// キューの状態（"queue-state" イベントのペイロード）
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct QueueState {
+    pub running: Option<JobInfo>,
+    /// 実行予定の順に並んだ待機中のジョブ
+    pub pending: Vec<JobInfo>,
+}
+
+type Task = Box<dyn FnOnce() + Send>;
+
+struct PendingJob {
+    info: JobInfo,
+    /// この時刻を過ぎるまで実行しない（自動バックアップの待ち時間など）
+    ready_at: Instant,
+    /// 待機中・実行中のどちらで取り消しても同じ要求を指す
+    cancel: CancelToken,
+    task: Task,
+}
+
+#[derive(Default)]
+struct Inner {
+    next_id: u64,
+    pending: Vec<PendingJob>,
+    running: Option<JobInfo>,
+    // 実行中のジョブへの取り消し要求
+    running_cancel: CancelToken,
+}
+
+thread_local! {
+    // ワーカースレッド上で実行中かどうか（ジョブ内から run_and_wait を呼んだ場合のデッドロックを防ぐ）
+    static IN_WORKER: Cell<bool> = const { Cell::new(false) };
+}
+
+/// バックアップ・リストア・削除・整理を1つのワーカースレッドで順に実行するキュー。
+/// 同じサブフォルダへの処理が同時に走ることがなく、オブジェクトの回収がバックアップ中の世代と競合することもありません。
+/// 複製しても同じキューを指します。
+#[derive(Clone)]
+pub struct JobQueue {
+    app_handle: AppHandle,
+    inner: Arc<(Mutex<Inner>, Condvar)>,
+}
+
+impl JobQueue {
+    /// キューを作成し、ワーカースレッドを開始する
+    pub fn new(app_handle: AppHandle) -> Self {
+        let queue = Self {
+            app_handle,
+            inner: Arc::new((Mutex::new(Inner::default()), Condvar::new())),
+        };
+        let worker = queue.clone();
+        thread::spawn(move || worker.run_worker());
+        queue
+    }
+
+    /// delay の経過後に実行するジョブを登録する。
+    /// 同じ種類・サブフォルダのジョブが待機中の場合は、新しい処理に置き換えて待ち時間をリセットします
+    /// （短時間に連続した変更を1回のバックアップにまとめる）。実行中のジョブとはまとめません。
+    pub fn enqueue(
+        &self,
+        kind: JobKind,
+        subfolder: Option<&str>,
+        delay: Duration,
+        task: impl FnOnce() + Send + 'static,
+    ) {
+        let ready_at = Instant::now() + delay;
+        {
+            let (lock, cvar) = &*self.inner;
+            let mut inner = lock.lock().unwrap();
+            let duplicate = inner
+                .pending
+                .iter_mut()
+                .find(|job| job.info.kind == kind && job.info.subfolder.as_deref() == subfolder);
+            match duplicate {
+                Some(job) => {
+                    job.ready_at = ready_at;
+                    job.task = Box::new(task);
+                }
+                None => {
+                    Self::push(&mut inner, kind, subfolder, ready_at, Box::new(task));
+                }
+            }
+            cvar.notify_one();
+        }
+        self.emit_state();
+    }
+
+    /// ジョブを登録し、実行が終わるまで待って結果を返す（他のジョブとはまとめない）。
+    /// ジョブの実行中に呼び出した場合は、順番を待たずにその場で実行します。
+    pub fn run_and_wait<T: Send + 'static>(
+        &self,
+        kind: JobKind,
+        subfolder: Option<&str>,
+        task: impl FnOnce() -> T + Send + 'static,
+    ) -> Result<T, Error> {
+        if IN_WORKER.with(Cell::get) {
+            return Ok(task());
+        }
+
+        let (tx, rx) = mpsc::channel();
+        let cancel = {
+            let (lock, cvar) = &*self.inner;
+            let mut inner = lock.lock().unwrap();
+            let task = move || {
+                let _ = tx.send(task());
+            };
+            let cancel = Self::push(&mut inner, kind, subfolder, Instant::now(), Box::new(task));
+            cvar.notify_one();
+            cancel
+        };
+        self.emit_state();
+
+        // ジョブが実行前に取り消された場合や異常終了した場合は送信側が破棄されるため、受信に失敗する
+        rx.recv().map_err(|_| {
+            if cancel.is_cancelled() {
+                Error::Cancelled("処理が取り消されました".to_string())
+            } else {
+                Error::Internal("処理が異常終了しました".to_string())
+            }
+        })
+    }
+
+    /// 指定したジョブを取り消す。待機中の場合はキューから取り除き、実行中の場合は取り消しを要求します
+    /// （進捗を通知する処理は、次のファイルへ進む前に中断して途中までの内容を削除する）。
+    /// 該当するジョブが無い場合は false を返します。
+    pub fn cancel(&self, job_id: u64) -> bool {
+        let found = {
+            let (lock, _) = &*self.inner;
+            let mut inner = lock.lock().unwrap();
+            if inner.running.as_ref().is_some_and(|job| job.id == job_id) {
+                inner.running_cancel.cancel();
+                true
+            } else if let Some(index) = inner.pending.iter().position(|job| job.info.id == job_id) {
+                inner.pending.remove(index).cancel.cancel();
+                true
+            } else {
+                false
+            }
+        };
+        if found {
+            println!("ジョブの取り消しを受け付けました: {}", job_id);
+            self.emit_state();
+        }
+        found
+    }
+
+    /// 実行中のジョブのIDと取り消し要求（ジョブの処理の中から、進捗の通知に使用する）
+    pub fn current_job(&self) -> Option<(u64, CancelToken)> {
+        let (lock, _) = &*self.inner;
+        let inner = lock.lock().unwrap();
+        inner
+            .running
+            .as_ref()
+            .map(|job| (job.id, inner.running_cancel.clone()))
+    }
+
+    /// 待機中のジョブのうち、指定した種類のものを取り消す（実行中のジョブはそのまま完了させる）
+    pub fn cancel_pending(&self, kind: JobKind) {
+        {
+            let (lock, _) = &*self.inner;
+            let mut inner = lock.lock().unwrap();
+            inner.pending.retain(|job| {
+                if job.info.kind == kind {
+                    job.cancel.cancel();
+                }
+                job.info.kind != kind
+            });
+        }
+        self.emit_state();
+    }
+
+    /// 現在のキューの状態
+    pub fn state(&self) -> QueueState {
+        let (lock, _) = &*self.inner;
+        let inner = lock.lock().unwrap();
+        let mut pending: Vec<&PendingJob> = inner.pending.iter().collect();
+        pending.sort_by_key(|job| (job.ready_at, job.info.id));
+        QueueState {
+            running: inner.running.clone(),
+            pending: pending.into_iter().map(|job| job.info.clone()).collect(),
+        }
+    }
+
+    /// キューの状態をフロントエンドへ通知する
+    pub fn emit_state(&self) {
+        let _ = self.app_handle.emit("queue-state", self.state());
+    }
+
+    fn push(
+        inner: &mut Inner,
+        kind: JobKind,
+        subfolder: Option<&str>,
+        ready_at: Instant,
+        task: Task,
+    ) -> CancelToken {
+        inner.next_id += 1;
+        let info = JobInfo {
+            id: inner.next_id,
+            kind,
+            subfolder: subfolder.map(str::to_string),
+            queued_at: Local::now().to_rfc3339(),
+        };
+        let cancel = CancelToken::default();
+        inner.pending.push(PendingJob {
+            info,
+            ready_at,
+            cancel: cancel.clone(),
+            task,
+        });
+        cancel
+    }
+
+    fn run_worker(&self) {
+        IN_WORKER.with(|flag| flag.set(true));
+        loop {
+            let job = self.next_job();
+            println!(
+                "ジョブを開始します: {:?} {:?}",
+                job.info.kind, job.info.subfolder
+            );
+            self.emit_state();
+
+            // ジョブが panic してもワーカーを止めず、後続のジョブを実行する
+            if panic::catch_unwind(AssertUnwindSafe(job.task)).is_err() {
+                println!("ジョブが異常終了しました: {:?}", job.info.kind);
+            }
+
+            {
+                let (lock, _) = &*self.inner;
+                lock.lock().unwrap().running = None;
+            }
+            self.emit_state();
+        }
+    }
+
+    /// 実行可能になったジョブを、実行予定の早い順（同時刻なら登録順）に1つ取り出す。
+    /// 無い場合は、次のジョブが実行可能になるか新しいジョブが登録されるまで待機します。
+    fn next_job(&self) -> PendingJob {
+        let (lock, cvar) = &*self.inner;
+        let mut inner = lock.lock().unwrap();
+        loop {
+            let next = inner
+                .pending
+                .iter()
+                .enumerate()
+                .min_by_key(|(_, job)| (job.ready_at, job.info.id))
+                .map(|(index, job)| (index, job.ready_at));
+            match next {
+                Some((index, ready_at)) => {
+                    let now = Instant::now();
+                    if ready_at <= now {
+                        let job = inner.pending.remove(index);
+                        inner.running = Some(job.info.clone());
+                        inner.running_cancel = job.cancel.clone();
+                        return job;
+                    }
+                    inner = cvar.wait_timeout(inner, ready_at - now).unwrap().0;
+                }
+                None => inner = cvar.wait(inner).unwrap(),
+            }
+        }
+    }
+}
@@ -1,26 +1,57 @@
+mod archive_store;
 mod backup_system;
+mod error;
+mod file_stability;
+mod generation;
+mod job_queue;
+mod object_store;
+mod path_filter;
+mod progress;
+mod quota;
+mod retention;
 mod settings_manager;
-
-use backup_system::BackupSystem;
-use settings_manager::SettingsManager;
+mod subfolder_meta;
+mod trash;
+mod watcher_health;
+
+use backup_system::{BackupSystem, RetentionCandidate};
+use error::Error;
+use generation::VerifyReport;
+use job_queue::{JobKind, QueueState};
+use retention::{RetentionTiers, RETENTION_MODE_COUNT, RETENTION_MODE_TIERED};
+use settings_manager::{
+    SettingsManager, STORAGE_FORMAT_ARCHIVE, STORAGE_FORMAT_OBJECTS, WATCHER_MODE_NATIVE,
+    WATCHER_MODE_POLL,
+};
+use subfolder_meta::SubfolderOverrides;
 use tauri::{
     menu::{Menu, MenuItem},
     tray::{MouseButton, TrayIconBuilder, TrayIconEvent},
-    Emitter, Manager, State,
+    Emitter, Listener, Manager, State,
 };
+use trash::TrashEntry;
+use watcher_health::WatcherState;
 
 #[tauri::command]
-fn initialize_app(app: tauri::AppHandle, state: State<'_, BackupSystem>) {
+fn initialize_app(app: tauri::AppHandle, state: State<'_, BackupSystem>) -> Result<(), Error> {
     // アプリ起動時の初期化処理
     // 1. 設定情報の現在値をフロントエンドへ通知
     let settings = state.get_settings();
     let _ = app.emit("settings-state", settings);
 
     // 2. バックアップの状態（フォルダ一覧や履歴）をスキャンしてフロントエンドへ通知
-    // 実行ファイルのディレクトリにある「Backups」フォルダをルートとして使用
-    let exe_path = std::env::current_exe().unwrap_or_default();
-    let backups_root = exe_path.parent().unwrap().join("Backups");
-    BackupSystem::emit_state(&app, &backups_root);
+    // 設定の backups_root（未設定時は実行ファイルのディレクトリの「Backups」）をルートとして使用
+    BackupSystem::emit_state(&app, &state.backups_root());
+
+    // 3. ゴミ箱の内容を通知
+    BackupSystem::emit_trash_state(&app, &state.backups_root());
+
+    // 4. 自動バックアップの一時停止の状態を通知
+    state.emit_watcher_state();
+
+    // 5. 待機中・実行中のジョブを通知
+    let _ = app.emit("queue-state", state.queue_state());
+    Ok(())
 }
 
 #[tauri::command]
@@ -29,17 +60,34 @@ fn save_settings(
     repo_path: String,
     max_generations: Option<usize>,
     theme: Option<String>,
-) {
+    storage_format: Option<String>,
+    pre_restore_retention_hours: Option<u64>,
+    retention_mode: Option<String>,
+    retention_tiers: Option<RetentionTiers>,
+    quota_total_mb: Option<u64>,
+    quota_subfolder_mb: Option<u64>,
+    trash_retention_days: Option<u64>,
+    write_wait_max_ms: Option<u64>,
+    debounce_ms: Option<u64>,
+    deletion_quiet_ms: Option<u64>,
+    min_backup_interval_minutes: Option<u64>,
+    include_patterns: Option<Vec<String>>,
+    exclude_patterns: Option<Vec<String>>,
+    watcher_mode: Option<String>,
+    poll_interval_ms: Option<u64>,
+) -> Result<(), Error> {
     // 設定の保存処理
+    // 実行中の設定を元に変更する（ファイルから読み直すと、保存後に変わった一時停止の状態などを古い値で上書きしてしまう）
     let mk = SettingsManager::new();
-    let mut current = mk.load();
+    let backup_system: tauri::State<BackupSystem> = app.state();
+    let mut current = backup_system.get_settings();
 
     // パスを更新
     current.repo_save_path = repo_path;
 
-    // 世代制限の上限を確認して反映 (1〜100)
+    // 数値の設定は、保存前に AppSettings::clamped で有効な範囲に丸める
     if let Some(gen) = max_generations {
-        current.max_generations = gen.clamp(1, 100);
+        current.max_generations = gen;
     }
 
     // テーマを反映
@@ -47,93 +95,502 @@ fn save_settings(
         current.theme = t;
     }
 
-    // ファイル（settings.ini）へ保存
-    mk.save(&current);
+    // 保存形式を反映（既知の形式のみ受け付ける。既存の世代はどちらの形式でも読み込み可能）
+    if let Some(format) = storage_format {
+        if format == STORAGE_FORMAT_OBJECTS || format == STORAGE_FORMAT_ARCHIVE {
+            current.storage_format = format;
+        }
+    }
 
-    // バックアップ監視システム側の設定をリアルタイムで同期
-    let backup_system: tauri::State<BackupSystem> = app.state();
+    // リストア前スナップショットの保護時間を反映
+    if let Some(hours) = pre_restore_retention_hours {
+        current.pre_restore_retention_hours = hours;
+    }
+
+    // 世代の整理方式と、段階的な保持の各段階の長さを反映
+    if let Some(mode) = retention_mode {
+        if mode == RETENTION_MODE_COUNT || mode == RETENTION_MODE_TIERED {
+            current.retention_mode = mode;
+        }
+    }
+    if let Some(tiers) = retention_tiers {
+        current.retention_tiers = tiers;
+    }
+
+    // 容量上限を反映 (MB 単位、0 は無制限)
+    if let Some(mb) = quota_total_mb {
+        current.quota_total_mb = mb;
+    }
+    if let Some(mb) = quota_subfolder_mb {
+        current.quota_subfolder_mb = mb;
+    }
+
+    // ゴミ箱の保管期間を反映
+    if let Some(days) = trash_retention_days {
+        current.trash_retention_days = days;
+    }
+
+    // 書き込み完了を待つ最大時間を反映 (0 は待たない)
+    if let Some(ms) = write_wait_max_ms {
+        current.write_wait_max_ms = ms;
+    }
+
+    // 変更検知の待ち時間と、削除直後の変更を無視する時間を反映
+    if let Some(ms) = debounce_ms {
+        current.debounce_ms = ms;
+    }
+    if let Some(ms) = deletion_quiet_ms {
+        current.deletion_quiet_ms = ms;
+    }
+
+    // 同じサブフォルダの自動バックアップの最小間隔を反映 (0 は制限なし)
+    if let Some(minutes) = min_backup_interval_minutes {
+        current.min_backup_interval_minutes = minutes;
+    }
+
+    // バックアップ対象・除外ファイルの glob パターンを反映（不正なパターンは clamped で取り除く）
+    if let Some(patterns) = include_patterns {
+        current.include_patterns = patterns;
+    }
+    if let Some(patterns) = exclude_patterns {
+        current.exclude_patterns = patterns;
+    }
+
+    // 監視方式（既知の方式のみ受け付ける）と、ポーリングの間隔を反映
+    if let Some(mode) = watcher_mode {
+        if mode == WATCHER_MODE_NATIVE || mode == WATCHER_MODE_POLL {
+            current.watcher_mode = mode;
+        }
+    }
+    if let Some(ms) = poll_interval_ms {
+        current.poll_interval_ms = ms;
+    }
+    let current = current.clamped();
+
+    // ファイル（settings.ini）へ保存（保存できなかった場合は何も反映せずにエラーを返す）
+    mk.save(&current)?;
+
+    // バックアップ監視システム側の設定をリアルタイムで同期（監視パス・方式が変わらない限りウォッチャーは再起動しない）
     backup_system.update_settings(current.clone());
 
     // フロントエンドへ最新の設定状態を通知
     let _ = app.emit("settings-state", current);
 
     // 監視対象パスが変更された可能性があるため、バックアップ一覧を再取得
-    let exe_path = std::env::current_exe().unwrap_or_default();
-    let backups_root = exe_path.parent().unwrap().join("Backups");
-    BackupSystem::emit_state(&app, &backups_root);
+    BackupSystem::emit_state(&app, &backup_system.backups_root());
+    Ok(())
+}
+
+#[tauri::command]
+fn pause_watching(state: State<'_, BackupSystem>, minutes: Option<u64>) -> Result<(), Error> {
+    // 自動バックアップを一時停止（minutes を指定した場合は、その時間の経過後に自動で再開。最大24時間）
+    // 設定ファイルへ保存できなかった場合も一時停止は有効になり、エラーを返す
+    state.pause_watching(minutes.map(|m| m.clamp(1, 1440)))
+}
+
+#[tauri::command]
+fn resume_watching(state: State<'_, BackupSystem>) -> Result<(), Error> {
+    state.resume_watching()
+}
+
+#[tauri::command]
+fn get_watcher_state(state: State<'_, BackupSystem>) -> Result<WatcherState, Error> {
+    Ok(state.watcher_state())
 }
 
 #[tauri::command]
-fn manual_backup(state: State<'_, BackupSystem>, subfolder_name: String) {
+fn get_queue_state(state: State<'_, BackupSystem>) -> Result<QueueState, Error> {
+    Ok(state.queue_state())
+}
+
+#[tauri::command]
+fn cancel_job(state: State<'_, BackupSystem>, job_id: u64) -> Result<(), Error> {
+    // 待機中のジョブはキューから取り除き、実行中のジョブは中断して途中までの内容を削除する
+    if state.cancel_job(job_id) {
+        Ok(())
+    } else {
+        Err(Error::NotFound(
+            "取り消せるジョブが見つかりません（既に完了している可能性があります）".to_string(),
+        ))
+    }
+}
+
+// 以下のキューを経由する操作は、先に登録されたジョブの完了を待つ間に画面が固まらないよう、
+// メインスレッド以外で実行する（async）
+
+#[tauri::command(async)]
+fn manual_backup(state: State<'_, BackupSystem>, subfolder_name: String) -> Result<(), Error> {
     // ユーザーがUIから「今すぐバックアップ」ボタンを押した際に呼ばれる
     // 引数 subfolder_name: バックアップ対象のサブフォルダ名（例: "SaveData1"）
-    state.trigger_backup(&subfolder_name);
+    // キューで順番に実行し、完了まで待って結果を返す（進行状況は queue-state イベントで伝えられる）
+    BackupSystem::validate_subfolder_name(&subfolder_name)?;
+    state.manual_backup(&subfolder_name)
 }
 
-#[tauri::command]
-fn restore_backup(state: State<'_, BackupSystem>, subfolder_name: String, timestamp: String) {
+#[tauri::command(async)]
+fn restore_backup(
+    state: State<'_, BackupSystem>,
+    subfolder_name: String,
+    timestamp: String,
+) -> Result<(), Error> {
     // ユーザーがUIから特定のバックアップを選択してリストアする際に呼ばれる
     // 引数 subfolder_name: リストア対象のサブフォルダ名
     // 引数 timestamp: リストアに使用するバックアップのタイムスタンプ（フォルダ名）
-    let settings = state.get_settings();
-    let exe_path = std::env::current_exe().unwrap_or_default();
-    let backups_root = exe_path.parent().unwrap().join("Backups");
+    // 失敗時はエラーメッセージを返す（現在のセーブデータはそのまま残る）
+    let system = state.inner().clone();
+    let subfolder = subfolder_name.clone();
+
+    state.run_job(JobKind::Restore, Some(&subfolder_name), move || {
+        // 順番を待つ間に設定が変わっている可能性があるため、実行時点の設定を使用する
+        let settings = system.get_settings();
+        let backups_root = settings.resolve_backups_root();
+        system.restore_backup(&settings, &backups_root, &subfolder, &timestamp)
+    })?
+}
+
+#[tauri::command(async)]
+fn undo_last_restore(state: State<'_, BackupSystem>, subfolder_name: String) -> Result<(), Error> {
+    // 直近のリストアを取り消し、リストア直前に自動保存されたセーブデータへ戻す
+    let system = state.inner().clone();
+    let subfolder = subfolder_name.clone();
 
-    state.restore_backup(&settings, &backups_root, &subfolder_name, &timestamp);
+    state.run_job(JobKind::Restore, Some(&subfolder_name), move || {
+        system.undo_last_restore(&subfolder)
+    })?
 }
 
 #[tauri::command]
-fn delete_backup(app: tauri::AppHandle, subfolder_name: String, timestamp: String) {
-    let exe_path = std::env::current_exe().unwrap_or_default();
-    let backups_root = exe_path.parent().unwrap().join("Backups");
+fn preview_retention(
+    state: State<'_, BackupSystem>,
+    subfolder_name: Option<String>,
+    retention_mode: Option<String>,
+    retention_tiers: Option<RetentionTiers>,
+    max_generations: Option<usize>,
+) -> Result<Vec<RetentionCandidate>, Error> {
+    // 整理方針を適用した場合に削除される世代を返す（実際には削除しない）
+    // 引数を省略した項目は現在の設定値を使用するため、保存前の設定を試算できる
+    let mut settings = state.get_settings();
+    if let Some(mode) = retention_mode {
+        settings.retention_mode = mode;
+    }
+    if let Some(tiers) = retention_tiers {
+        settings.retention_tiers = tiers;
+    }
+    if let Some(gen) = max_generations {
+        settings.max_generations = gen;
+    }
+    // 保存時と同じ範囲に丸めてから、サブフォルダごとの設定を含めて実際の整理と同じ方針で試算する
+    let settings = settings.clamped();
+
+    Ok(BackupSystem::preview_retention(
+        &settings,
+        subfolder_name.as_deref(),
+    ))
+}
 
-    BackupSystem::delete_backup(&app, &backups_root, &subfolder_name, &timestamp);
+#[tauri::command(async)]
+fn delete_backup(
+    app: tauri::AppHandle,
+    state: State<'_, BackupSystem>,
+    subfolder_name: String,
+    timestamp: String,
+    force: Option<bool>,
+) -> Result<(), Error> {
+    // 世代はゴミ箱へ移動され、restore_from_trash で元に戻せる
+    // 固定された世代は force: true を指定しない限り削除しない
+    let backups_root = state.backups_root();
+    let (job_app, job_root, subfolder) =
+        (app.clone(), backups_root.clone(), subfolder_name.clone());
+
+    let result = state.run_job(JobKind::Delete, Some(&subfolder_name), move || {
+        BackupSystem::delete_backup(
+            &job_app,
+            &job_root,
+            &subfolder,
+            &timestamp,
+            force.unwrap_or(false),
+        )
+    });
     BackupSystem::emit_state(&app, &backups_root);
+    BackupSystem::emit_trash_state(&app, &backups_root);
+    result?
 }
 
-#[tauri::command]
-fn delete_subfolder(app: tauri::AppHandle, subfolder_name: String) {
-    let exe_path = std::env::current_exe().unwrap_or_default();
-    let backups_root = exe_path.parent().unwrap().join("Backups");
+#[tauri::command(async)]
+fn set_generation_pinned(
+    app: tauri::AppHandle,
+    state: State<'_, BackupSystem>,
+    subfolder_name: String,
+    timestamp: String,
+    pinned: bool,
+) -> Result<(), Error> {
+    // 世代を固定すると、世代制限による自動削除の対象外となる
+    // （整理・削除と同時にメタ情報を書き換えないよう、キューで順番に実行する）
+    let backups_root = state.backups_root();
+    let (job_root, subfolder) = (backups_root.clone(), subfolder_name.clone());
+
+    let result = state.run_job(JobKind::Annotate, Some(&subfolder_name), move || {
+        BackupSystem::set_generation_pinned(&job_root, &subfolder, &timestamp, pinned)
+    });
+    BackupSystem::emit_state(&app, &backups_root);
+    result?
+}
 
-    BackupSystem::delete_subfolder(&app, &backups_root, &subfolder_name);
+#[tauri::command(async)]
+fn delete_subfolder(
+    app: tauri::AppHandle,
+    state: State<'_, BackupSystem>,
+    subfolder_name: String,
+) -> Result<(), Error> {
+    // サブフォルダのすべての世代をまとめてゴミ箱へ移動する
+    let backups_root = state.backups_root();
+    let (job_app, job_root, subfolder) =
+        (app.clone(), backups_root.clone(), subfolder_name.clone());
+
+    let result = state.run_job(JobKind::Delete, Some(&subfolder_name), move || {
+        BackupSystem::delete_subfolder(&job_app, &job_root, &subfolder)
+    });
     BackupSystem::emit_state(&app, &backups_root);
+    BackupSystem::emit_trash_state(&app, &backups_root);
+    result?
 }
 
 #[tauri::command]
-fn save_memo(app: tauri::AppHandle, subfolder_name: String, memo_content: String) {
+fn list_trash(state: State<'_, BackupSystem>) -> Result<Vec<TrashEntry>, Error> {
+    // ゴミ箱内の項目を新しい順で返す（保管期間を過ぎた項目は完全に削除される）
+    let settings = state.get_settings();
+
+    Ok(BackupSystem::list_trash(
+        &settings.resolve_backups_root(),
+        settings.trash_retention_days,
+    ))
+}
+
+#[tauri::command(async)]
+fn restore_from_trash(
+    app: tauri::AppHandle,
+    state: State<'_, BackupSystem>,
+    trash_id: String,
+) -> Result<(), Error> {
+    // ゴミ箱の項目を元のサブフォルダへ戻す（同じ世代が既にある場合はエラー）
+    let backups_root = state.backups_root();
+    let (job_app, job_root) = (app.clone(), backups_root.clone());
+
+    let result = state.run_job(JobKind::Delete, None, move || {
+        BackupSystem::restore_from_trash(&job_app, &job_root, &trash_id)
+    });
+    BackupSystem::emit_state(&app, &backups_root);
+    BackupSystem::emit_trash_state(&app, &backups_root);
+    result?
+}
+
+#[tauri::command(async)]
+fn empty_trash(
+    app: tauri::AppHandle,
+    state: State<'_, BackupSystem>,
+    trash_id: Option<String>,
+) -> Result<(), Error> {
+    // ゴミ箱の項目を完全に削除する。trash_id を省略した場合はゴミ箱を空にする
+    // （オブジェクトの回収がバックアップ中の世代と競合しないよう、キューで順番に実行する）
+    let backups_root = state.backups_root();
+    let (job_app, job_root) = (app.clone(), backups_root.clone());
+
+    let result = state.run_job(JobKind::Delete, None, move || {
+        BackupSystem::empty_trash(&job_app, &job_root, trash_id.as_deref())
+    });
+    BackupSystem::emit_trash_state(&app, &backups_root);
+    result?
+}
+
+#[tauri::command(async)]
+fn verify_backups(
+    app: tauri::AppHandle,
+    state: State<'_, BackupSystem>,
+    subfolder_name: Option<String>,
+    timestamp: Option<String>,
+) -> Result<Vec<VerifyReport>, Error> {
+    // 指定した範囲（省略時はすべて）の世代を再ハッシュして整合性を検証する
+    // （整理・GC と同時に走ると回収直後のオブジェクトを欠落と誤判定するため、キューで順番に実行する）
+    let backups_root = state.backups_root();
+    let (job_app, job_root) = (app.clone(), backups_root.clone());
+    let job_subfolder = subfolder_name.clone();
+
+    let result = state.run_job(JobKind::Verify, job_subfolder.as_deref(), move || {
+        BackupSystem::verify_backups(
+            &job_app,
+            &job_root,
+            subfolder_name.as_deref(),
+            timestamp.as_deref(),
+        )
+    });
+    // 検証結果（verified / corrupt）を一覧へ反映
+    BackupSystem::emit_state(&app, &backups_root);
+    result
+}
+
+#[tauri::command(async)]
+fn save_memo(
+    app: tauri::AppHandle,
+    state: State<'_, BackupSystem>,
+    subfolder_name: String,
+    memo_content: String,
+) -> Result<(), Error> {
     // サブフォルダ（カテゴリ）ごとのメモを meta.json に保存する
-    let exe_path = std::env::current_exe().unwrap_or_default();
-    let backups_root = exe_path.parent().unwrap().join("Backups");
+    // （同じ meta.json を書き換える設定の保存と競合しないよう、キューで順番に実行する）
+    let backups_root = state.backups_root();
+    let (job_root, subfolder) = (backups_root.clone(), subfolder_name.clone());
 
-    BackupSystem::save_memo(&backups_root, &subfolder_name, &memo_content);
+    let result = state.run_job(JobKind::Annotate, Some(&subfolder_name), move || {
+        BackupSystem::save_memo(&job_root, &subfolder, &memo_content)
+    });
     // 保存後、UIを即座に更新するために状態を再送
     BackupSystem::emit_state(&app, &backups_root);
+    result?
+}
+
+#[tauri::command(async)]
+fn set_subfolder_overrides(
+    app: tauri::AppHandle,
+    state: State<'_, BackupSystem>,
+    subfolder_name: String,
+    overrides: SubfolderOverrides,
+) -> Result<(), Error> {
+    // サブフォルダごとの設定（保持件数・自動バックアップ・待ち時間・除外ファイル）を meta.json に保存する
+    // 未指定（null）の項目は全体設定に従う
+    let backups_root = state.backups_root();
+    let (job_root, subfolder) = (backups_root.clone(), subfolder_name.clone());
+
+    let result = state.run_job(JobKind::Annotate, Some(&subfolder_name), move || {
+        BackupSystem::set_subfolder_overrides(&job_root, &subfolder, overrides)
+    });
+    BackupSystem::emit_state(&app, &backups_root);
+    result?
+}
+
+#[tauri::command(async)]
+fn set_generation_memo(
+    app: tauri::AppHandle,
+    state: State<'_, BackupSystem>,
+    subfolder_name: String,
+    timestamp: String,
+    memo: String,
+) -> Result<(), Error> {
+    // 特定の世代（時点）に対するメモを、世代ごとのメタ情報に保存する
+    annotate_generation(&app, &state, subfolder_name, timestamp, Some(memo), None)
+}
+
+#[tauri::command(async)]
+fn set_generation_tags(
+    app: tauri::AppHandle,
+    state: State<'_, BackupSystem>,
+    subfolder_name: String,
+    timestamp: String,
+    tags: Vec<String>,
+) -> Result<(), Error> {
+    // 特定の世代に付けるタグを置き換える（空白のみのタグや重複は取り除かれる）
+    annotate_generation(&app, &state, subfolder_name, timestamp, None, Some(tags))
+}
+
+#[tauri::command(async)]
+fn clear_generation_annotations(
+    app: tauri::AppHandle,
+    state: State<'_, BackupSystem>,
+    subfolder_name: String,
+    timestamp: String,
+) -> Result<(), Error> {
+    // 特定の世代のメモとタグをすべて消去する
+    annotate_generation(
+        &app,
+        &state,
+        subfolder_name,
+        timestamp,
+        Some(String::new()),
+        Some(Vec::new()),
+    )
+}
+
+/// 世代のメモ・タグの更新を、整理・削除と同時に行わないようキューで実行し、一覧へ反映する
+fn annotate_generation(
+    app: &tauri::AppHandle,
+    state: &BackupSystem,
+    subfolder_name: String,
+    timestamp: String,
+    memo: Option<String>,
+    tags: Option<Vec<String>>,
+) -> Result<(), Error> {
+    let backups_root = state.backups_root();
+    let job_root = backups_root.clone();
+
+    let result = state.run_job(JobKind::Annotate, Some(&subfolder_name), move || {
+        BackupSystem::annotate_generation(&job_root, &subfolder_name, &timestamp, memo, tags)
+    });
+    BackupSystem::emit_state(app, &backups_root);
+    result?
+}
+
+#[tauri::command(async)]
+fn migrate_backups_root(state: State<'_, BackupSystem>, new_root: String) -> Result<(), Error> {
+    // バックアップの保存先を変更し、既存のストアを新しい場所へ移動する
+    // 進捗は "operation-progress" イベントで通知され、失敗時・取り消し時は元の場所と設定が維持される
+    if new_root.trim().is_empty() {
+        return Err(Error::InvalidArgument(
+            "移行先のフォルダを指定してください".to_string(),
+        ));
+    }
+    // 移行中にバックアップが旧ルートへ書き込まれないよう、他のジョブと同じキューで実行する
+    let system = state.inner().clone();
+    state.run_job(JobKind::Migration, None, move || {
+        system.migrate_backups_root(std::path::Path::new(&new_root))
+    })?
 }
 
 #[cfg(debug_assertions)]
 #[tauri::command]
-fn test_notification(app: tauri::AppHandle) {
+fn test_notification(app: tauri::AppHandle) -> Result<(), Error> {
     println!("[DEBUG COMMAND] test_notification called");
     // 開発時のデバッグ用コマンド。実際のバックアップ生成時と同じ流れで通知を表示します。
     BackupSystem::send_notification(&app, "テスト通知", "これはデバッグ用のテスト通知です。");
+    Ok(())
 }
 
-#[tauri::command]
-fn open_path_in_explorer(app: tauri::AppHandle, path: String) {
+/// フォルダをエクスプローラで開きます（存在しない場合はエラー）。
+fn open_folder(app: &tauri::AppHandle, path: &std::path::Path) -> Result<(), Error> {
     use tauri_plugin_opener::OpenerExt;
-    let _ = app.opener().open_path(path, None::<&str>);
+    if !path.exists() {
+        return Err(Error::NotFound(format!(
+            "フォルダが見つかりません: {}",
+            path.display()
+        )));
+    }
+    app.opener()
+        .open_path(path.to_string_lossy().to_string(), None::<&str>)
+        .map_err(|e| {
+            Error::Internal(format!(
+                "フォルダを開けませんでした: {} ({})",
+                path.display(),
+                e
+            ))
+        })
+}
+
+#[tauri::command]
+fn open_path_in_explorer(app: tauri::AppHandle, path: String) -> Result<(), Error> {
+    open_folder(&app, std::path::Path::new(&path))
 }
 
 /// バックアップルートフォルダをエクスプローラで開きます。
 #[tauri::command]
-fn open_backups_folder(app: tauri::AppHandle) {
-    use tauri_plugin_opener::OpenerExt;
-    let exe_path = std::env::current_exe().unwrap_or_default();
-    let backups_root = exe_path.parent().unwrap().join("Backups");
-    let _ = app
-        .opener()
-        .open_path(backups_root.to_string_lossy().to_string(), None::<&str>);
+fn open_backups_folder(app: tauri::AppHandle, state: State<'_, BackupSystem>) -> Result<(), Error> {
+    open_folder(&app, &state.backups_root())
+}
+
+/// トレイメニューの一時停止・再開の項目に表示する文言
+fn pause_menu_text(paused: bool) -> &'static str {
+    if paused {
+        "自動バックアップを再開"
+    } else {
+        "自動バックアップを一時停止"
+    }
 }
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -217,7 +674,23 @@ pub fn run() {
             let quit_i = MenuItem::with_id(app, "quit", "終了", true, None::<&str>)?;
             let settings_i =
                 MenuItem::with_id(app, "settings", "設定画面を開く", true, None::<&str>)?;
-            let menu = Menu::with_items(app, &[&settings_i, &quit_i])?;
+            let pause_i = MenuItem::with_id(
+                app,
+                "toggle_pause",
+                pause_menu_text(app.state::<BackupSystem>().watcher_state().paused),
+                true,
+                None::<&str>,
+            )?;
+            let menu = Menu::with_items(app, &[&pause_i, &settings_i, &quit_i])?;
+
+            // 一時停止の状態が変わったら（画面・期限切れ・トレイのいずれから変更された場合も）表示を切り替える
+            app.listen_any("watcher-state", move |event| {
+                let paused = serde_json::from_str::<serde_json::Value>(event.payload())
+                    .ok()
+                    .and_then(|state| state["paused"].as_bool())
+                    .unwrap_or(false);
+                let _ = pause_i.set_text(pause_menu_text(paused));
+            });
 
             let _tray = TrayIconBuilder::new()
                 .icon(app.default_window_icon().unwrap().clone())
@@ -227,6 +700,23 @@ pub fn run() {
                         // アプリを完全に終了
                         app.exit(0);
                     }
+                    "toggle_pause" => {
+                        // 自動バックアップの一時停止・再開を切り替え（トレイからは再開するまで停止）
+                        let backup_system: tauri::State<BackupSystem> = app.state();
+                        let result = if backup_system.watcher_state().paused {
+                            backup_system.resume_watching()
+                        } else {
+                            backup_system.pause_watching(None)
+                        };
+                        // 切り替え自体は反映済み。トレイには結果を返す先が無いため、通知で伝える
+                        if let Err(e) = result {
+                            BackupSystem::send_notification(
+                                app,
+                                "監視の設定の保存失敗",
+                                &e.to_string(),
+                            );
+                        }
+                    }
                     "settings" => {
                         // 設定画面を前面に表示
                         if let Some(win) = app.get_webview_window("main") {
@@ -272,11 +762,28 @@ pub fn run() {
         .invoke_handler(tauri::generate_handler![
             initialize_app,
             save_settings,
+            pause_watching,
+            resume_watching,
+            get_watcher_state,
+            get_queue_state,
+            cancel_job,
             manual_backup,
             restore_backup,
+            undo_last_restore,
+            preview_retention,
             delete_backup,
+            set_generation_pinned,
             delete_subfolder,
+            list_trash,
+            restore_from_trash,
+            empty_trash,
+            verify_backups,
             save_memo,
+            set_subfolder_overrides,
+            set_generation_memo,
+            set_generation_tags,
+            clear_generation_annotations,
+            migrate_backups_root,
             open_path_in_explorer,
             open_backups_folder,
             #[cfg(debug_assertions)]
@@ -0,0 +1,416 @@
+use crate::progress::Progress;
+use crate::trash::Trash;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeSet, HashSet};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Mutex;
+use walkdir::WalkDir;
+
+/// バックアップルート直下に置かれる、内容アドレス方式のオブジェクト格納フォルダ名
+pub const OBJECTS_DIR_NAME: &str = ".objects";
+/// 各世代フォルダ内に置かれる、パスとハッシュの対応表のファイル名
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+/// オブジェクト格納フォルダ直下に置かれる、書き込み中のオブジェクトの一時ファイル名
+const INCOMING_FILE_NAME: &str = "incoming.tmp";
+
+// オブジェクトの書き込みとガベージコレクションが同時に走ると、
+// マニフェスト書き込み前のオブジェクトを誤って削除してしまうため、ストア全体で直列化する
+static STORE_LOCK: Mutex<()> = Mutex::new(());
+
+/// バックアップ対象に含めるファイルかどうかを、マニフェスト上のパスで判定する関数
+pub type FileFilter<'a> = &'a dyn Fn(&str) -> bool;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ManifestEntry {
+    /// 世代ルートからの相対パス（区切り文字は常に "/"）
+    pub path: String,
+    /// ファイル内容の SHA-256（16進文字列）
+    pub hash: String,
+    /// ファイルサイズ（バイト）
+    pub size: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Manifest {
+    pub files: Vec<ManifestEntry>,
+}
+
+impl Manifest {
+    /// ファイル構成（パスとハッシュの組）が完全に一致するかどうか
+    pub fn same_content(&self, other: &Manifest) -> bool {
+        let as_set = |m: &Manifest| {
+            m.files
+                .iter()
+                .map(|f| (f.path.clone(), f.hash.clone()))
+                .collect::<BTreeSet<_>>()
+        };
+        as_set(self) == as_set(other)
+    }
+}
+
+/// マニフェストと実際の内容を突き合わせた結果（いずれもマニフェスト上のパス）
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct IntegrityIssues {
+    /// マニフェストに記載されているが、実体が存在しないファイル
+    pub missing: Vec<String>,
+    /// マニフェストに記載されていないのに存在するファイル
+    pub extra: Vec<String>,
+    /// サイズまたはハッシュがマニフェストと一致しないファイル
+    pub corrupted: Vec<String>,
+}
+
+impl IntegrityIssues {
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty() && self.corrupted.is_empty()
+    }
+}
+
+/// ファイル内容のハッシュをキーとして一度だけ保存する、重複排除型のバックアップストア。
+/// 各世代はファイルのコピーではなく、パスとハッシュの対応表（manifest.json）として記録されます。
+pub struct ObjectStore {
+    backups_root: PathBuf,
+}
+
+impl ObjectStore {
+    pub fn new(backups_root: &Path) -> Self {
+        Self {
+            backups_root: backups_root.to_path_buf(),
+        }
+    }
+
+    fn objects_dir(&self) -> PathBuf {
+        self.backups_root.join(OBJECTS_DIR_NAME)
+    }
+
+    /// ハッシュ値からオブジェクトの格納パスを求める（先頭2文字でフォルダを分散）。
+    /// SHA-256 の16進文字列でない値は、格納フォルダの外を指さないよう拒否します。
+    fn object_path(&self, hash: &str) -> io::Result<PathBuf> {
+        if hash.len() != 64 || !hash.bytes().all(|b| matches!(b, b'0'..=b'9' | b'a'..=b'f')) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!("不正なハッシュ値です: {}", hash),
+            ));
+        }
+        let (prefix, rest) = hash.split_at(2);
+        Ok(self.objects_dir().join(prefix).join(rest))
+    }
+
+    /// 指定した世代フォルダが、このストア形式（manifest.json を持つ）かどうか
+    pub fn is_generation(generation_dir: &Path) -> bool {
+        generation_dir.join(MANIFEST_FILE_NAME).is_file()
+    }
+
+    pub fn read_manifest(generation_dir: &Path) -> io::Result<Manifest> {
+        let content = fs::read_to_string(generation_dir.join(MANIFEST_FILE_NAME))?;
+        serde_json::from_str(&content).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    /// ファイルの SHA-256 を計算する
+    pub fn hash_file(path: &Path) -> io::Result<String> {
+        let mut file = fs::File::open(path)?;
+        let mut hasher = Sha256::new();
+        io::copy(&mut file, &mut hasher)?;
+        Ok(hex::encode(hasher.finalize()))
+    }
+
+    /// メモリ上のデータの SHA-256 を計算する
+    pub fn hash_bytes(data: &[u8]) -> String {
+        hex::encode(Sha256::digest(data))
+    }
+
+    /// src 配下の全ファイルをハッシュし、格納は行わずにマニフェストだけを作成する
+    pub fn scan_dir(src: &Path) -> io::Result<Manifest> {
+        Self::scan_dir_filtered(src, &|_| true)
+    }
+
+    /// scan_dir と同様だが、include が false を返すファイルは含めない
+    pub fn scan_dir_filtered(src: &Path, include: FileFilter) -> io::Result<Manifest> {
+        let mut manifest = Manifest::default();
+        for entry in WalkDir::new(src).sort_by_file_name() {
+            let entry = entry.map_err(io::Error::from)?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let rel_path = to_manifest_path(entry.path().strip_prefix(src).unwrap());
+            if !include(&rel_path) {
+                continue;
+            }
+            manifest.files.push(ManifestEntry {
+                path: rel_path,
+                hash: Self::hash_file(entry.path())?,
+                size: entry.metadata().map_err(io::Error::from)?.len(),
+            });
+        }
+        Ok(manifest)
+    }
+
+    /// src 配下のファイル（include が true のもの）をオブジェクトとして格納し、
+    /// generation_dir に manifest.json を書き出す。
+    /// 既に同じ内容のオブジェクトが存在する場合はコピーを省略します。
+    /// 取り消された場合は manifest.json を書き出さずに中断します（格納済みのオブジェクトは回収の対象となる）。
+    pub fn store_dir(
+        &self,
+        src: &Path,
+        generation_dir: &Path,
+        include: FileFilter,
+        progress: &Progress,
+    ) -> io::Result<Manifest> {
+        let _guard = STORE_LOCK.lock().unwrap();
+
+        let files = list_files(src, include)?;
+        progress.start(files.len() as u64, files.iter().map(|file| file.size).sum());
+
+        let mut manifest = Manifest::default();
+        for file in files {
+            progress.check()?;
+            let (hash, size) = self.store_object(&file.path)?;
+
+            progress.advance(file.size);
+            manifest.files.push(ManifestEntry {
+                path: file.rel_path,
+                hash,
+                size,
+            });
+        }
+
+        fs::create_dir_all(generation_dir)?;
+        let json = serde_json::to_string_pretty(&manifest)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(generation_dir.join(MANIFEST_FILE_NAME), json)?;
+
+        Ok(manifest)
+    }
+
+    /// ファイルを一時ファイルへコピーしながらハッシュを計算し、そのハッシュのオブジェクトとして配置する。
+    /// ハッシュとコピーを同じ読み込みで行うため、途中でファイルが書き換えられても内容とハッシュが食い違いません。
+    /// 格納したオブジェクトのハッシュとサイズを返します（同じ内容のオブジェクトが既にあれば一時ファイルは破棄）。
+    fn store_object(&self, src: &Path) -> io::Result<(String, u64)> {
+        fs::create_dir_all(self.objects_dir())?;
+        let tmp_path = self.objects_dir().join(INCOMING_FILE_NAME);
+        let result = (|| {
+            let mut writer = HashingWriter {
+                file: fs::File::create(&tmp_path)?,
+                hasher: Sha256::new(),
+            };
+            let size = io::copy(&mut fs::File::open(src)?, &mut writer)?;
+            let hash = hex::encode(writer.hasher.finalize());
+
+            let object_path = self.object_path(&hash)?;
+            if object_path.exists() {
+                fs::remove_file(&tmp_path)?;
+            } else {
+                fs::create_dir_all(object_path.parent().unwrap())?;
+                fs::rename(&tmp_path, &object_path)?;
+            }
+            Ok((hash, size))
+        })();
+        if result.is_err() {
+            let _ = fs::remove_file(&tmp_path);
+        }
+        result
+    }
+
+    /// manifest.json に従ってオブジェクトを dest へ展開する
+    pub fn restore_dir(
+        &self,
+        generation_dir: &Path,
+        dest: &Path,
+        progress: &Progress,
+    ) -> io::Result<()> {
+        let manifest = Self::read_manifest(generation_dir)?;
+        fs::create_dir_all(dest)?;
+        progress.start(
+            manifest.files.len() as u64,
+            manifest.files.iter().map(|file| file.size).sum(),
+        );
+
+        for file in &manifest.files {
+            progress.check()?;
+            let Some(rel_path) = from_manifest_path(&file.path) else {
+                println!("不正なパスをスキップしました: {}", file.path);
+                continue;
+            };
+            let dest_path = dest.join(rel_path);
+            if let Some(parent) = dest_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::copy(self.object_path(&file.hash)?, dest_path)?;
+            progress.advance(file.size);
+        }
+        Ok(())
+    }
+
+    /// manifest.json が参照するオブジェクトを再ハッシュし、欠落・破損を検出する
+    pub fn verify_dir(&self, generation_dir: &Path) -> io::Result<IntegrityIssues> {
+        let manifest = Self::read_manifest(generation_dir)?;
+        let mut issues = IntegrityIssues::default();
+
+        for file in &manifest.files {
+            let Ok(object_path) = self.object_path(&file.hash) else {
+                issues.corrupted.push(file.path.clone());
+                continue;
+            };
+            let Ok(metadata) = fs::metadata(&object_path) else {
+                issues.missing.push(file.path.clone());
+                continue;
+            };
+            if metadata.len() != file.size || Self::hash_file(&object_path)? != file.hash {
+                issues.corrupted.push(file.path.clone());
+            }
+        }
+
+        // 世代フォルダにはマニフェストのみが置かれるため、それ以外のファイルは余分なものとして報告
+        for entry in WalkDir::new(generation_dir).min_depth(1) {
+            let entry = entry.map_err(io::Error::from)?;
+            if entry.file_type().is_file() && entry.file_name() != MANIFEST_FILE_NAME {
+                let rel_path = entry.path().strip_prefix(generation_dir).unwrap();
+                issues.extra.push(to_manifest_path(rel_path));
+            }
+        }
+
+        Ok(issues)
+    }
+
+    /// どの世代からも参照されていないオブジェクトを削除し、削除した件数を返す
+    pub fn collect_garbage(&self) -> io::Result<usize> {
+        let _guard = STORE_LOCK.lock().unwrap();
+
+        let objects_dir = self.objects_dir();
+        if !objects_dir.exists() {
+            return Ok(0);
+        }
+
+        // 1. 全サブフォルダの全世代のマニフェストから、参照中のハッシュを収集
+        // ゴミ箱内の世代も元に戻せるよう、参照中として扱う
+        let mut referenced = HashSet::new();
+        Self::collect_references(&self.backups_root, &mut referenced)?;
+        for trash_entry in Trash::entry_dirs(&self.backups_root) {
+            Self::collect_references(&trash_entry, &mut referenced)?;
+        }
+
+        // 2. 参照されていないオブジェクトを削除
+        let mut removed = 0;
+        for entry in WalkDir::new(&objects_dir).min_depth(2).max_depth(2) {
+            let entry = entry.map_err(io::Error::from)?;
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let prefix = entry
+                .path()
+                .parent()
+                .and_then(|p| p.file_name())
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let hash = format!("{}{}", prefix, entry.file_name().to_string_lossy());
+            if !referenced.contains(&hash) {
+                fs::remove_file(entry.path())?;
+                removed += 1;
+            }
+        }
+
+        Ok(removed)
+    }
+
+    /// root 直下の各サブフォルダにある世代のマニフェストから、参照中のハッシュを収集する
+    fn collect_references(root: &Path, referenced: &mut HashSet<String>) -> io::Result<()> {
+        for subfolder in fs::read_dir(root)?.filter_map(|e| e.ok()) {
+            if subfolder.file_name().to_string_lossy().starts_with('.')
+                || !subfolder.path().is_dir()
+            {
+                continue;
+            }
+            let Ok(generations) = fs::read_dir(subfolder.path()) else {
+                continue;
+            };
+            for generation in generations.filter_map(|e| e.ok()) {
+                if !Self::is_generation(&generation.path()) {
+                    continue;
+                }
+                match Self::read_manifest(&generation.path()) {
+                    Ok(manifest) => referenced.extend(manifest.files.into_iter().map(|f| f.hash)),
+                    Err(e) => {
+                        // 読めないマニフェストがある状態で削除すると復元不能になるため、中断する
+                        println!(
+                            "マニフェストの読み込みに失敗したため GC を中断します: {:?}",
+                            e
+                        );
+                        return Err(e);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 書き込んだ内容のハッシュを同時に計算するファイル
+struct HashingWriter {
+    file: fs::File,
+    hasher: Sha256,
+}
+
+impl Write for HashingWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.file.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// 世代に格納する対象のファイル
+pub struct SourceFile {
+    pub path: PathBuf,
+    /// マニフェスト上のパス
+    pub rel_path: String,
+    pub size: u64,
+}
+
+/// src 配下のファイルのうち include が true のものを、パスの順に列挙する（進捗の合計を先に求めるため）
+pub fn list_files(src: &Path, include: FileFilter) -> io::Result<Vec<SourceFile>> {
+    let mut files = Vec::new();
+    for entry in WalkDir::new(src).sort_by_file_name() {
+        let entry = entry.map_err(io::Error::from)?;
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let rel_path = to_manifest_path(entry.path().strip_prefix(src).unwrap());
+        if !include(&rel_path) {
+            continue;
+        }
+        files.push(SourceFile {
+            size: entry.metadata().map_err(io::Error::from)?.len(),
+            path: entry.into_path(),
+            rel_path,
+        });
+    }
+    Ok(files)
+}
+
+/// ローカルの相対パスを、OS に依存しないマニフェスト上の表記（"/" 区切り）へ変換する
+pub fn to_manifest_path(rel_path: &Path) -> String {
+    rel_path
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+/// マニフェスト上のパスをローカルパスへ変換する。ルート外を指すパスは拒否します。
+fn from_manifest_path(path: &str) -> Option<PathBuf> {
+    let rel_path = PathBuf::from_iter(path.split('/'));
+    if rel_path
+        .components()
+        .all(|c| matches!(c, Component::Normal(_)))
+    {
+        Some(rel_path)
+    } else {
+        None
+    }
+}
@@ -0,0 +1,86 @@
+use globset::{Glob, GlobBuilder, GlobSet, GlobSetBuilder};
+
+/// 全体設定の除外パターンの既定値（ゲームが保存時に作成する一時ファイル・バックアップ・ログ）
+pub const DEFAULT_EXCLUDE_PATTERNS: [&str; 3] = ["*.tmp", "*.bak", "*.log"];
+
+/// サブフォルダ内のファイルを、マニフェスト上のパス（"/" 区切り）に対する glob パターンで選別するフィルタ。
+/// 監視イベントによるバックアップの判定と、世代へのファイルの格納の両方で使用します。
+///
+/// パターンは、相対パス全体・ファイル名（パターンに "/" を含まない場合）・親フォルダのいずれかに
+/// 一致すれば該当とみなします（"*" は "/" をまたがず、"**" で任意の階層に一致）。
+pub struct PathFilter {
+    /// None の場合はすべてのファイルを対象とする
+    include: Option<GlobSet>,
+    exclude: GlobSet,
+}
+
+impl PathFilter {
+    /// 対象パターン（空の場合はすべて）と除外パターンからフィルタを作成する。不正なパターンは無視します。
+    pub fn new(include: &[String], exclude: &[String]) -> Self {
+        Self {
+            include: (!include.is_empty()).then(|| build_set(include)),
+            exclude: build_set(exclude),
+        }
+    }
+
+    /// マニフェスト上のパスがバックアップの対象かどうか（対象パターンに一致し、除外パターンに一致しない）
+    pub fn includes(&self, rel_path: &str) -> bool {
+        if matches(&self.exclude, rel_path) {
+            return false;
+        }
+        match &self.include {
+            Some(include) => matches(include, rel_path),
+            None => true,
+        }
+    }
+}
+
+/// パターンを glob として解釈する（"*" や "?" がフォルダの区切りをまたがないようにする）
+fn parse(pattern: &str) -> Result<Glob, globset::Error> {
+    GlobBuilder::new(pattern).literal_separator(true).build()
+}
+
+fn build_set(patterns: &[String]) -> GlobSet {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        match parse(pattern) {
+            Ok(glob) => {
+                builder.add(glob);
+            }
+            Err(e) => println!("不正なパターンを無視しました: {} ({})", pattern, e),
+        }
+    }
+    builder.build().unwrap_or_else(|_| GlobSet::empty())
+}
+
+/// 相対パス全体・ファイル名・各親フォルダのいずれかがパターンに一致するかどうか
+fn matches(set: &GlobSet, rel_path: &str) -> bool {
+    if set.is_empty() {
+        return false;
+    }
+    let file_name = rel_path.rsplit('/').next().unwrap_or(rel_path);
+    set.is_match(rel_path)
+        || set.is_match(file_name)
+        || rel_path
+            .match_indices('/')
+            .any(|(i, _)| set.is_match(&rel_path[..i]))
+}
+
+/// パターンの前後の空白・区切り文字を整え、空・重複・glob として不正なものを取り除く
+pub fn normalize_patterns(patterns: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for pattern in patterns {
+        // 区切り文字はマニフェストと同じ "/" に揃える
+        let pattern = pattern.trim().replace('\\', "/");
+        let pattern = pattern.trim_matches('/').to_string();
+        if pattern.is_empty() || normalized.contains(&pattern) {
+            continue;
+        }
+        if let Err(e) = parse(&pattern) {
+            println!("不正なパターンを無視しました: {} ({})", pattern, e);
+            continue;
+        }
+        normalized.push(pattern);
+    }
+    normalized
+}
@@ -0,0 +1,169 @@
+use serde::Serialize;
+use std::cell::Cell;
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tauri::{AppHandle, Emitter};
+
+/// 進捗イベントを発行する最短の間隔（大量の小さなファイルで画面の更新が追いつかなくなるのを防ぐ）
+const EMIT_INTERVAL: Duration = Duration::from_millis(100);
+
+/// 実行中の処理の取り消し要求。複製しても同じ要求を指します。
+#[derive(Clone, Default, Debug)]
+pub struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub fn cancel(&self) {
+        self.0.store(true, Ordering::SeqCst);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::SeqCst)
+    }
+}
+
+/// 取り消しによって処理を中断したことを表すエラーかどうか
+pub fn is_cancelled(error: &io::Error) -> bool {
+    error.kind() == io::ErrorKind::Interrupted
+}
+
+/// 進捗を通知する処理の種類
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Operation {
+    /// 世代の作成（リストア前スナップショットを含む）
+    Backup,
+    /// 世代の展開
+    Restore,
+    /// バックアップの保存先の移行
+    Migration,
+}
+
+/// 処理の状態
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ProgressStatus {
+    Running,
+    Completed,
+    /// 取り消し要求により中断し、途中までの内容を削除した
+    Cancelled,
+    /// エラーにより中断し、途中までの内容を削除した
+    Failed,
+}
+
+/// 進捗（"operation-progress" イベントのペイロード）
+#[derive(Serialize, Clone, Debug)]
+pub struct ProgressEvent {
+    /// 処理を実行しているジョブのID（cancel_job に指定する）。キューを経由しない処理は None
+    pub job_id: Option<u64>,
+    pub operation: Operation,
+    pub subfolder: Option<String>,
+    pub status: ProgressStatus,
+    pub files_done: u64,
+    pub files_total: u64,
+    pub bytes_done: u64,
+    pub bytes_total: u64,
+}
+
+/// 長時間かかるコピー・展開の進捗を数え、一定間隔でフロントエンドへ通知する。
+/// 取り消しが要求されている場合は、check で Interrupted のエラーを返して処理を中断させます。
+pub struct Progress {
+    app_handle: AppHandle,
+    job_id: Option<u64>,
+    cancel: CancelToken,
+    operation: Operation,
+    subfolder: Option<String>,
+    files_done: Cell<u64>,
+    files_total: Cell<u64>,
+    bytes_done: Cell<u64>,
+    bytes_total: Cell<u64>,
+    last_emit: Cell<Option<Instant>>,
+}
+
+impl Progress {
+    /// job は実行中のジョブのIDと取り消し要求（キューを経由しない場合は None）
+    pub fn new(
+        app_handle: &AppHandle,
+        job: Option<(u64, CancelToken)>,
+        operation: Operation,
+        subfolder: Option<&str>,
+    ) -> Self {
+        let (job_id, cancel) = match job {
+            Some((id, token)) => (Some(id), token),
+            None => (None, CancelToken::default()),
+        };
+        Self {
+            app_handle: app_handle.clone(),
+            job_id,
+            cancel,
+            operation,
+            subfolder: subfolder.map(str::to_string),
+            files_done: Cell::new(0),
+            files_total: Cell::new(0),
+            bytes_done: Cell::new(0),
+            bytes_total: Cell::new(0),
+            last_emit: Cell::new(None),
+        }
+    }
+
+    /// 処理対象のファイル数と合計サイズを設定し、開始を通知する
+    pub fn start(&self, files_total: u64, bytes_total: u64) {
+        self.files_done.set(0);
+        self.bytes_done.set(0);
+        self.files_total.set(files_total);
+        self.bytes_total.set(bytes_total);
+        self.emit(ProgressStatus::Running);
+    }
+
+    /// 取り消しが要求されていれば Interrupted のエラーを返す
+    pub fn check(&self) -> io::Result<()> {
+        if self.cancel.is_cancelled() {
+            return Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                "処理が取り消されました",
+            ));
+        }
+        Ok(())
+    }
+
+    /// 1ファイル分の処理が終わったことを記録する（前回の通知から一定時間が経過していれば通知）
+    pub fn advance(&self, bytes: u64) {
+        self.files_done.set(self.files_done.get() + 1);
+        self.bytes_done.set(self.bytes_done.get() + bytes);
+        let due = self
+            .last_emit
+            .get()
+            .is_none_or(|at| at.elapsed() >= EMIT_INTERVAL);
+        if due {
+            self.emit(ProgressStatus::Running);
+        }
+    }
+
+    /// 処理の結果を通知する（途中までの内容の削除は呼び出し側で行う）
+    pub fn finish<T>(&self, result: &io::Result<T>) {
+        let status = match result {
+            Ok(_) => ProgressStatus::Completed,
+            Err(e) if is_cancelled(e) => ProgressStatus::Cancelled,
+            Err(_) => ProgressStatus::Failed,
+        };
+        self.emit(status);
+    }
+
+    fn emit(&self, status: ProgressStatus) {
+        self.last_emit.set(Some(Instant::now()));
+        let _ = self.app_handle.emit(
+            "operation-progress",
+            ProgressEvent {
+                job_id: self.job_id,
+                operation: self.operation,
+                subfolder: self.subfolder.clone(),
+                status,
+                files_done: self.files_done.get(),
+                files_total: self.files_total.get(),
+                bytes_done: self.bytes_done.get(),
+                bytes_total: self.bytes_total.get(),
+            },
+        );
+    }
+}
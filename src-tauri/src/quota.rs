@@ -0,0 +1,225 @@
+use crate::generation::{Generation, GenerationLayout};
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use walkdir::WalkDir;
+
+/// 使用量が上限のこの割合を超えたら警告する（%）
+pub const QUOTA_WARNING_PERCENT: u64 = 90;
+
+/// バックアップストアの容量制限（バイト単位。None は無制限）
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Quota {
+    /// バックアップルート全体の上限
+    pub total_bytes: Option<u64>,
+    /// サブフォルダごとの上限
+    pub subfolder_bytes: Option<u64>,
+}
+
+impl Quota {
+    /// 設定値（MB、0 は無制限）から容量制限を作成する
+    pub fn from_mb(total_mb: u64, subfolder_mb: u64) -> Self {
+        let to_bytes = |mb: u64| (mb > 0).then(|| mb.saturating_mul(1024 * 1024));
+        Self {
+            total_bytes: to_bytes(total_mb),
+            subfolder_bytes: to_bytes(subfolder_mb),
+        }
+    }
+}
+
+/// 使用量が上限の警告ラインを超えているかどうか
+pub fn is_near_limit(usage: u64, limit: u64) -> bool {
+    usage.saturating_mul(100) >= limit.saturating_mul(QUOTA_WARNING_PERCENT)
+}
+
+/// バックアップルート全体のディスク使用量（オブジェクトストアや内部管理用ファイルを含む）
+pub fn store_usage(backups_root: &Path) -> u64 {
+    dir_size(backups_root)
+}
+
+/// サブフォルダの世代が占めるディスク使用量。
+/// 重複排除形式の世代は、参照しているオブジェクトを（世代間で重複させずに）合算します。
+/// 他のサブフォルダと共有しているオブジェクトも含むため、削除で解放される量とは一致しない場合があります。
+pub fn subfolder_usage(folder_path: &Path) -> u64 {
+    let mut objects: HashMap<String, u64> = HashMap::new();
+    let mut bytes = 0;
+    for generation in Generation::list(folder_path) {
+        match generation.layout {
+            GenerationLayout::Objects => {
+                if let Ok(manifest) = generation.manifest() {
+                    objects.extend(manifest.files.into_iter().map(|f| (f.hash, f.size)));
+                }
+            }
+            GenerationLayout::Archive => {
+                bytes += generation.path.metadata().map(|m| m.len()).unwrap_or(0);
+            }
+            GenerationLayout::Directory => bytes += dir_size(&generation.path),
+        }
+    }
+    bytes + objects.values().sum::<u64>()
+}
+
+/// 世代（またはゴミ箱の項目）を削除した場合に解放される容量を見積もるための内訳
+#[derive(Default)]
+pub struct Footprint {
+    /// オブジェクトストアの外で占めている容量（アーカイブ・旧形式のフォルダ・ゴミ箱の項目フォルダ）
+    own_bytes: u64,
+    /// 参照しているオブジェクトのハッシュとサイズ（重複なし）
+    objects: HashMap<String, u64>,
+}
+
+impl Footprint {
+    /// 世代の内訳。重複排除形式の世代フォルダ自体（マニフェストのみ）は小さいため数えません。
+    pub fn of_generation(generation: &Generation) -> Self {
+        let mut footprint = Self::default();
+        footprint.add_generation(generation);
+        footprint
+    }
+
+    /// ゴミ箱の項目フォルダの内訳（項目フォルダ全体と、項目内の世代が参照するオブジェクト）
+    pub fn of_trash_entry(entry_dir: &Path) -> Self {
+        let mut footprint = Self::default();
+        let subfolders = fs::read_dir(entry_dir).into_iter().flatten();
+        for subfolder in subfolders.filter_map(|e| e.ok()) {
+            for generation in Generation::list(&subfolder.path()) {
+                footprint.add_generation(&generation);
+            }
+        }
+        footprint.own_bytes = dir_size(entry_dir);
+        footprint
+    }
+
+    fn add_generation(&mut self, generation: &Generation) {
+        match generation.layout {
+            GenerationLayout::Objects => {
+                if let Ok(manifest) = generation.manifest() {
+                    self.objects
+                        .extend(manifest.files.into_iter().map(|f| (f.hash, f.size)));
+                }
+            }
+            GenerationLayout::Archive => {
+                self.own_bytes += generation.path.metadata().map(|m| m.len()).unwrap_or(0);
+            }
+            GenerationLayout::Directory => self.own_bytes += dir_size(&generation.path),
+        }
+    }
+}
+
+/// candidates（holders のインデックス、削除する順）を先頭から削除した場合に、
+/// 使用量 usage が上限 limit 以下になる最小の件数を返す。
+/// holders はオブジェクトを参照しているすべての世代・項目（候補を含む）で、他から参照され続けるオブジェクトは解放量に数えません。
+/// すべての候補を削除しても上限を下回らない場合は None を返します（無駄に世代を削除しないため）。
+pub fn plan_removals(
+    usage: u64,
+    limit: u64,
+    holders: &[Footprint],
+    candidates: &[usize],
+) -> Option<usize> {
+    if usage <= limit {
+        return Some(0);
+    }
+    let mut references: HashMap<&str, usize> = HashMap::new();
+    for holder in holders {
+        for hash in holder.objects.keys() {
+            *references.entry(hash.as_str()).or_default() += 1;
+        }
+    }
+
+    let mut freed = 0u64;
+    for (count, &index) in candidates.iter().enumerate() {
+        let candidate = &holders[index];
+        freed += candidate.own_bytes;
+        for (hash, size) in &candidate.objects {
+            if let Some(remaining) = references.get_mut(hash.as_str()) {
+                *remaining -= 1;
+                if *remaining == 0 {
+                    freed += size;
+                }
+            }
+        }
+        if usage.saturating_sub(freed) <= limit {
+            return Some(count + 1);
+        }
+    }
+    None
+}
+
+fn dir_size(path: &Path) -> u64 {
+    WalkDir::new(path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| e.metadata().ok())
+        .map(|m| m.len())
+        .sum()
+}
+
+/// 表示用にバイト数を MB 単位の文字列へ変換する
+pub fn format_mb(bytes: u64) -> String {
+    format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// 世代外の容量と、参照するオブジェクト（ハッシュ, サイズ）から内訳を作成する
+    fn footprint(own_bytes: u64, objects: &[(&str, u64)]) -> Footprint {
+        Footprint {
+            own_bytes,
+            objects: objects
+                .iter()
+                .map(|(hash, size)| (hash.to_string(), *size))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn within_limit_removes_nothing() {
+        let holders = [footprint(100, &[])];
+        assert_eq!(plan_removals(100, 100, &holders, &[0]), Some(0));
+    }
+
+    #[test]
+    fn removes_oldest_until_under_limit() {
+        let holders = [
+            footprint(0, &[("a", 100)]),
+            footprint(0, &[("b", 100)]),
+            footprint(0, &[("c", 100)]),
+        ];
+        assert_eq!(plan_removals(300, 200, &holders, &[0, 1]), Some(1));
+        assert_eq!(plan_removals(300, 100, &holders, &[0, 1]), Some(2));
+    }
+
+    #[test]
+    fn shared_objects_are_freed_only_with_the_last_holder() {
+        // "shared" は候補0・1と、削除しない2が参照しているため、候補を削除しても解放されない
+        let holders = [
+            footprint(0, &[("shared", 500), ("a", 10)]),
+            footprint(0, &[("shared", 500), ("b", 10)]),
+            footprint(0, &[("shared", 500)]),
+        ];
+        assert_eq!(plan_removals(520, 505, &holders, &[0, 1]), Some(2));
+        assert_eq!(plan_removals(520, 400, &holders, &[0, 1]), None);
+
+        // 参照する世代がすべて候補であれば、最後の1件の削除で解放される
+        let holders = [
+            footprint(0, &[("shared", 500)]),
+            footprint(0, &[("shared", 500)]),
+        ];
+        assert_eq!(plan_removals(500, 0, &holders, &[0, 1]), Some(2));
+    }
+
+    #[test]
+    fn own_bytes_count_towards_freed_space() {
+        // ゴミ箱の項目や zip 形式の世代は、オブジェクトを参照していなくても容量を解放する
+        let holders = [footprint(300, &[]), footprint(300, &[("a", 50)])];
+        assert_eq!(plan_removals(650, 400, &holders, &[0, 1]), Some(1));
+    }
+
+    #[test]
+    fn unreachable_limit_removes_nothing() {
+        let holders = [footprint(0, &[("a", 100)]), footprint(0, &[("b", 100)])];
+        assert_eq!(plan_removals(200, 50, &holders, &[0]), None);
+    }
+}
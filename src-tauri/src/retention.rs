@@ -0,0 +1,239 @@
+use chrono::{DateTime, Duration, Local, Months, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// 保持件数のみで世代を整理する方式（既定）
+pub const RETENTION_MODE_COUNT: &str = "count";
+/// 経過時間に応じて間引きながら世代を残す方式（世代別保持）
+pub const RETENTION_MODE_TIERED: &str = "tiered";
+
+/// 段階的な保持方式の各段階の長さ。
+/// いずれも作成からの経過時間の上限で、その範囲内の世代を各単位で1つずつ（最新のもの）残します。
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RetentionTiers {
+    /// この時間内の世代はすべて残す
+    pub keep_all_hours: u64,
+    /// この時間内の世代は1時間ごとに1つ残す
+    pub hourly_hours: u64,
+    /// この日数内の世代は1日ごとに1つ残す
+    pub daily_days: u64,
+    /// この月数内の世代は1週間ごとに1つ残す（これより古い世代は削除）
+    pub weekly_months: u64,
+}
+
+impl Default for RetentionTiers {
+    fn default() -> Self {
+        Self {
+            keep_all_hours: 1,
+            hourly_hours: 24,
+            daily_days: 7,
+            weekly_months: 3,
+        }
+    }
+}
+
+impl RetentionTiers {
+    /// 設定値を有効な範囲に丸める。
+    /// すべて 0 にすると作成直後の世代まで期限切れになるため、すべて残す時間は最低1時間とします。
+    pub fn clamped(self) -> Self {
+        Self {
+            keep_all_hours: self.keep_all_hours.clamp(1, 168),
+            hourly_hours: self.hourly_hours.min(720),
+            daily_days: self.daily_days.min(365),
+            weekly_months: self.weekly_months.min(120),
+        }
+    }
+}
+
+/// 世代の整理方針
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RetentionPolicy {
+    /// 新しい順に指定件数だけ残す
+    Count(usize),
+    /// 経過時間に応じて 全件 → 1時間ごと → 1日ごと → 1週間ごと と間引いて残す
+    Tiered(RetentionTiers),
+}
+
+impl RetentionPolicy {
+    /// 削除すべき世代の位置を返す。
+    /// created_at は各世代の作成日時（UNIX 秒）で、古い順に並んでいること。
+    /// 設定値にかかわらず、最も新しい世代は削除の対象にしません。
+    pub fn expired(&self, created_at: &[i64], now: DateTime<Local>) -> Vec<usize> {
+        let newest = created_at.len().checked_sub(1);
+        let expired = match self {
+            RetentionPolicy::Count(limit) => {
+                let to_remove = created_at.len().saturating_sub(*limit);
+                (0..to_remove).collect()
+            }
+            RetentionPolicy::Tiered(tiers) => Self::expired_tiered(tiers, created_at, now),
+        };
+        expired
+            .into_iter()
+            .filter(|&index| Some(index) != newest)
+            .collect()
+    }
+
+    fn expired_tiered(
+        tiers: &RetentionTiers,
+        created_at: &[i64],
+        now: DateTime<Local>,
+    ) -> Vec<usize> {
+        let keep_all_until = now - Duration::hours(tiers.keep_all_hours as i64);
+        let hourly_until = now - Duration::hours(tiers.hourly_hours as i64);
+        let daily_until = now - Duration::days(tiers.daily_days as i64);
+        let weekly_until = now
+            .checked_sub_months(Months::new(tiers.weekly_months as u32))
+            .unwrap_or(now);
+
+        // 新しい順に走査し、各段階の単位（時・日・週）ごとに最初に現れた世代（= 最新）を残す
+        let mut seen_buckets = HashSet::new();
+        let mut expired = Vec::new();
+        for (index, &timestamp) in created_at.iter().enumerate().rev() {
+            // 作成日時が不明な世代（0）は判断できないため残す
+            let Some(created) = Local
+                .timestamp_opt(timestamp, 0)
+                .single()
+                .filter(|_| timestamp > 0)
+            else {
+                continue;
+            };
+
+            let bucket = if created >= keep_all_until {
+                continue;
+            } else if created >= hourly_until {
+                created.format("h%Y%m%d%H").to_string()
+            } else if created >= daily_until {
+                created.format("d%Y%m%d").to_string()
+            } else if created >= weekly_until {
+                created.format("w%G%V").to_string()
+            } else {
+                expired.push(index);
+                continue;
+            };
+
+            if !seen_buckets.insert(bucket) {
+                expired.push(index);
+            }
+        }
+        expired.sort();
+        expired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: i64 = 3600;
+
+    fn now() -> DateTime<Local> {
+        Local.timestamp_opt(1_700_000_000, 0).unwrap()
+    }
+
+    /// now から指定した秒数だけ前の作成日時を、古い順に並べる
+    fn ages(seconds_ago: &[i64]) -> Vec<i64> {
+        let mut created_at: Vec<i64> = seconds_ago.iter().map(|s| now().timestamp() - s).collect();
+        created_at.sort();
+        created_at
+    }
+
+    #[test]
+    fn count_keeps_newest_generations() {
+        let created_at = ages(&[50, 40, 30, 20, 10]);
+        assert_eq!(
+            RetentionPolicy::Count(2).expired(&created_at, now()),
+            vec![0, 1, 2]
+        );
+        assert!(RetentionPolicy::Count(10)
+            .expired(&created_at, now())
+            .is_empty());
+    }
+
+    #[test]
+    fn count_zero_never_expires_newest() {
+        let created_at = ages(&[30, 20, 10]);
+        assert_eq!(
+            RetentionPolicy::Count(0).expired(&created_at, now()),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn empty_history_expires_nothing() {
+        assert!(RetentionPolicy::Count(0).expired(&[], now()).is_empty());
+        let tiers = RetentionTiers::default();
+        assert!(RetentionPolicy::Tiered(tiers)
+            .expired(&[], now())
+            .is_empty());
+    }
+
+    #[test]
+    fn tiered_keeps_everything_within_keep_all() {
+        let created_at = ages(&[50 * 60, 30 * 60, 10 * 60, 60]);
+        let tiers = RetentionTiers::default();
+        assert!(RetentionPolicy::Tiered(tiers)
+            .expired(&created_at, now())
+            .is_empty());
+    }
+
+    #[test]
+    fn tiered_thins_older_generations() {
+        // 2時間前付近の世代は同じ時間帯のため最新の1つだけ、数か月前の世代は期限切れ
+        let base = now().timestamp() - now().timestamp() % HOUR;
+        let created_at = vec![
+            base - 200 * 24 * HOUR,
+            base - 2 * HOUR + 60,
+            base - 2 * HOUR + 120,
+            now().timestamp() - 60,
+        ];
+        let tiers = RetentionTiers::default();
+        assert_eq!(
+            RetentionPolicy::Tiered(tiers).expired(&created_at, now()),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn tiered_with_zero_tiers_keeps_newest() {
+        // 検証前の設定値がすべて 0 でも、直前に作成した世代は残る
+        let created_at = ages(&[3 * HOUR, 2 * HOUR, HOUR]);
+        let tiers = RetentionTiers {
+            keep_all_hours: 0,
+            hourly_hours: 0,
+            daily_days: 0,
+            weekly_months: 0,
+        };
+        assert_eq!(
+            RetentionPolicy::Tiered(tiers).expired(&created_at, now()),
+            vec![0, 1]
+        );
+    }
+
+    #[test]
+    fn clamped_requires_keep_all_hour() {
+        let tiers = RetentionTiers {
+            keep_all_hours: 0,
+            hourly_hours: 0,
+            daily_days: 0,
+            weekly_months: 0,
+        }
+        .clamped();
+        assert_eq!(tiers.keep_all_hours, 1);
+
+        // 作成直後（1時間以内）の世代はすべて残る
+        let created_at = ages(&[50 * 60, 30 * 60, 10 * 60]);
+        assert!(RetentionPolicy::Tiered(tiers)
+            .expired(&created_at, now())
+            .is_empty());
+    }
+
+    #[test]
+    fn unknown_creation_time_is_kept() {
+        let created_at = vec![0, now().timestamp() - 300 * 24 * HOUR, now().timestamp()];
+        let tiers = RetentionTiers::default();
+        assert_eq!(
+            RetentionPolicy::Tiered(tiers).expired(&created_at, now()),
+            vec![1]
+        );
+    }
+}
@@ -1,9 +1,30 @@
+use crate::error::Error;
+use crate::path_filter::{normalize_patterns, DEFAULT_EXCLUDE_PATTERNS};
+use crate::quota::Quota;
+use crate::retention::{
+    RetentionPolicy, RetentionTiers, RETENTION_MODE_COUNT, RETENTION_MODE_TIERED,
+};
+use chrono::{DateTime, Local};
 use ini::Ini;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
 
 const SETTINGS_FILE: &str = "settings.ini";
+/// backups_root が未設定の場合に、実行ファイルのディレクトリ配下に作成するフォルダ名
+const DEFAULT_BACKUPS_DIR_NAME: &str = "Backups";
+/// INI ファイル上で、パターンの一覧を1行に保存する際の区切り文字（glob の "{a,b}" と衝突しない文字）
+const PATTERN_SEPARATOR: char = ';';
+
+/// 重複排除のオブジェクトストアへ保存する形式（既定）
+pub const STORAGE_FORMAT_OBJECTS: &str = "objects";
+/// 1世代を1つの zip ファイルとして保存する形式
+pub const STORAGE_FORMAT_ARCHIVE: &str = "archive";
+
+/// OS の変更通知でフォルダを監視する方式（既定。利用できない場合はポーリングへ自動で切り替え）
+pub const WATCHER_MODE_NATIVE: &str = "native";
+/// 一定間隔でフォルダを走査して変更を検知する方式（ネットワークドライブなど、変更通知が届かない環境向け）
+pub const WATCHER_MODE_POLL: &str = "poll";
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
 pub struct AppSettings {
@@ -12,6 +33,131 @@ pub struct AppSettings {
     pub theme: String, // "dark", "light", "system"
     #[serde(default = "default_max_generations")]
     pub max_generations: usize,
+    #[serde(default = "default_storage_format")]
+    pub storage_format: String, // "objects", "archive"
+    /// バックアップの保存先ルート。空の場合は実行ファイルと同じディレクトリの "Backups"
+    #[serde(default)]
+    pub backups_root: String,
+    /// リストア前スナップショットを世代制限による削除から保護する時間
+    #[serde(default = "default_pre_restore_retention_hours")]
+    pub pre_restore_retention_hours: u64,
+    /// 世代の整理方式（"count": max_generations 件を保持、"tiered": 経過時間に応じて間引く）
+    #[serde(default = "default_retention_mode")]
+    pub retention_mode: String,
+    /// retention_mode が "tiered" の場合の各段階の長さ
+    #[serde(default)]
+    pub retention_tiers: RetentionTiers,
+    /// バックアップルート全体の容量上限（MB、0 は無制限）
+    #[serde(default)]
+    pub quota_total_mb: u64,
+    /// サブフォルダごとの容量上限（MB、0 は無制限）
+    #[serde(default)]
+    pub quota_subfolder_mb: u64,
+    /// ゴミ箱へ移動した世代を完全に削除するまでの日数
+    #[serde(default = "default_trash_retention_days")]
+    pub trash_retention_days: u64,
+    /// バックアップ前に、セーブデータの書き込み完了（サイズ・更新日時が落ち着くまで）を待つ最大時間（ミリ秒、0 は待たない）
+    #[serde(default = "default_write_wait_max_ms")]
+    pub write_wait_max_ms: u64,
+    /// 変更イベントが途絶えてからバックアップするまでの待ち時間（ミリ秒）。サブフォルダごとに上書き可能
+    #[serde(default = "default_debounce_ms")]
+    pub debounce_ms: u64,
+    /// サブフォルダの削除直後に発生した変更を、削除に伴うものとして無視する時間（ミリ秒）
+    #[serde(default = "default_deletion_quiet_ms")]
+    pub deletion_quiet_ms: u64,
+    /// 同じサブフォルダの自動バックアップの最小間隔（分、0 は制限なし）。間隔内の変更は最後の状態を1世代にまとめる
+    #[serde(default)]
+    pub min_backup_interval_minutes: u64,
+    /// バックアップ対象とするファイルの glob パターン（空の場合はすべて）。サブフォルダごとに上書き可能
+    #[serde(default)]
+    pub include_patterns: Vec<String>,
+    /// バックアップ対象から除外するファイルの glob パターン。除外したファイルの変更ではバックアップしない。
+    /// 既定のパターンは新規インストール時のみ設定し、既存の設定（項目が無い場合を含む）には追加しない
+    #[serde(default)]
+    pub exclude_patterns: Vec<String>,
+    /// セーブデータフォルダの監視方式（"native", "poll"）
+    #[serde(default = "default_watcher_mode")]
+    pub watcher_mode: String,
+    /// ポーリングで監視する場合の走査間隔（ミリ秒）
+    #[serde(default = "default_poll_interval_ms")]
+    pub poll_interval_ms: u64,
+    /// 変更検知による自動バックアップを一時停止しているかどうか（再起動後も維持される）
+    #[serde(default)]
+    pub auto_backup_paused: bool,
+    /// 一時停止を自動的に解除する日時（RFC 3339）。空の場合は再開するまで停止したまま
+    #[serde(default)]
+    pub auto_backup_paused_until: String,
+}
+
+impl AppSettings {
+    /// バックアップの保存先ルートを解決する。
+    /// バックアップルートを参照する処理はすべてこの関数を経由すること。
+    pub fn resolve_backups_root(&self) -> PathBuf {
+        if self.backups_root.trim().is_empty() {
+            exe_dir().join(DEFAULT_BACKUPS_DIR_NAME)
+        } else {
+            PathBuf::from(&self.backups_root)
+        }
+    }
+
+    /// 現在の設定に基づく世代の整理方針
+    pub fn retention_policy(&self) -> RetentionPolicy {
+        if self.retention_mode == RETENTION_MODE_TIERED {
+            RetentionPolicy::Tiered(self.retention_tiers)
+        } else {
+            RetentionPolicy::Count(self.max_generations)
+        }
+    }
+
+    /// 自動バックアップが一時停止中かどうか（解除日時を過ぎている場合は停止していないものとみなす）
+    pub fn is_auto_backup_paused(&self) -> bool {
+        if !self.auto_backup_paused {
+            return false;
+        }
+        match DateTime::parse_from_rfc3339(&self.auto_backup_paused_until) {
+            Ok(until) => Local::now() < until,
+            Err(_) => true,
+        }
+    }
+
+    /// 現在の設定に基づく容量制限
+    pub fn quota(&self) -> Quota {
+        Quota::from_mb(self.quota_total_mb, self.quota_subfolder_mb)
+    }
+
+    /// 数値の設定を有効な範囲に丸め、不正な glob パターンを取り除く。
+    /// 画面からの保存と settings.ini の読み込みの両方で使用し、手で編集された値も同じ範囲に収めます。
+    pub fn clamped(mut self) -> Self {
+        // 世代制限 (1〜100)、リストア前スナップショットの保護時間 (1時間〜30日)
+        self.max_generations = self.max_generations.clamp(1, 100);
+        self.pre_restore_retention_hours = self.pre_restore_retention_hours.clamp(1, 720);
+        self.retention_tiers = self.retention_tiers.clamped();
+        // 容量上限 (MB 単位、0 は無制限。上限は 1TB)
+        self.quota_total_mb = self.quota_total_mb.min(1024 * 1024);
+        self.quota_subfolder_mb = self.quota_subfolder_mb.min(1024 * 1024);
+        // ゴミ箱の保管期間 (1〜365日)
+        self.trash_retention_days = self.trash_retention_days.clamp(1, 365);
+        // 書き込み完了を待つ最大時間 (0〜120秒、0 は待たない)
+        self.write_wait_max_ms = self.write_wait_max_ms.min(120_000);
+        // 変更検知の待ち時間 (50ms〜60秒) と、削除直後の変更を無視する時間 (0〜10秒)
+        self.debounce_ms = self.debounce_ms.clamp(50, 60_000);
+        self.deletion_quiet_ms = self.deletion_quiet_ms.min(10_000);
+        // 同じサブフォルダの自動バックアップの最小間隔 (0〜24時間、0 は制限なし)
+        self.min_backup_interval_minutes = self.min_backup_interval_minutes.min(1440);
+        // ポーリングの間隔 (0.5〜60秒)
+        self.poll_interval_ms = self.poll_interval_ms.clamp(500, 60_000);
+        self.include_patterns = normalize_patterns(self.include_patterns);
+        self.exclude_patterns = normalize_patterns(self.exclude_patterns);
+        self
+    }
+}
+
+/// 実行ファイルのあるディレクトリ（取得できない場合はカレントディレクトリ）
+fn exe_dir() -> PathBuf {
+    match std::env::current_exe().ok() {
+        Some(path) => path.parent().unwrap_or(Path::new(".")).to_path_buf(),
+        None => PathBuf::from("."),
+    }
 }
 
 fn default_theme() -> String {
@@ -22,6 +168,58 @@ fn default_max_generations() -> usize {
     10
 }
 
+fn default_pre_restore_retention_hours() -> u64 {
+    24
+}
+
+fn default_trash_retention_days() -> u64 {
+    30
+}
+
+fn default_write_wait_max_ms() -> u64 {
+    10_000
+}
+
+fn default_debounce_ms() -> u64 {
+    300
+}
+
+fn default_deletion_quiet_ms() -> u64 {
+    500
+}
+
+fn default_exclude_patterns() -> Vec<String> {
+    DEFAULT_EXCLUDE_PATTERNS
+        .iter()
+        .map(|p| p.to_string())
+        .collect()
+}
+
+/// INI ファイルに1行で保存されたパターンの一覧を分割する
+fn parse_patterns(value: &str) -> Vec<String> {
+    value
+        .split(PATTERN_SEPARATOR)
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
+fn default_watcher_mode() -> String {
+    WATCHER_MODE_NATIVE.to_string()
+}
+
+fn default_poll_interval_ms() -> u64 {
+    2_000
+}
+
+fn default_retention_mode() -> String {
+    RETENTION_MODE_COUNT.to_string()
+}
+
+fn default_storage_format() -> String {
+    STORAGE_FORMAT_OBJECTS.to_string()
+}
+
 impl Default for AppSettings {
     fn default() -> Self {
         // 開発元の仕様に基づくデフォルトのセーブデータパス
@@ -38,6 +236,24 @@ impl Default for AppSettings {
             repo_save_path: default_path.to_string_lossy().to_string(), // 初期パス
             theme: "system".to_string(),                                // 初期テーマ
             max_generations: 10,                                        // 初期保持世代数
+            storage_format: default_storage_format(),                   // 初期保存形式
+            backups_root: String::new(), // 初期保存先（実行ファイルのディレクトリ）
+            pre_restore_retention_hours: default_pre_restore_retention_hours(), // 初期保護時間
+            retention_mode: default_retention_mode(), // 初期整理方式（件数で保持）
+            retention_tiers: RetentionTiers::default(),
+            quota_total_mb: 0,     // 初期容量上限（無制限）
+            quota_subfolder_mb: 0, // 初期容量上限（無制限）
+            trash_retention_days: default_trash_retention_days(), // 初期ゴミ箱の保管期間
+            write_wait_max_ms: default_write_wait_max_ms(), // 初期書き込み完了の最大待ち時間
+            debounce_ms: default_debounce_ms(), // 初期変更検知の待ち時間
+            deletion_quiet_ms: default_deletion_quiet_ms(), // 初期削除直後の無視時間
+            min_backup_interval_minutes: 0, // 初期最小間隔（制限なし）
+            include_patterns: Vec::new(), // 初期対象パターン（すべて）
+            exclude_patterns: default_exclude_patterns(), // 初期除外パターン（一時ファイルなど）
+            watcher_mode: default_watcher_mode(), // 初期監視方式（OS の変更通知）
+            poll_interval_ms: default_poll_interval_ms(), // 初期ポーリング間隔
+            auto_backup_paused: false, // 初期状態（自動バックアップ有効）
+            auto_backup_paused_until: String::new(),
         }
     }
 }
@@ -51,12 +267,7 @@ impl SettingsManager {
         // In dev, use current dir. In production, use exe dir.
         // For simplicity in Tauri v2, we can just use std::env::current_exe() parent or standard dirs.
         // The plan said "app executable directory".
-        let exe_path = std::env::current_exe().ok();
-        let dir = match exe_path {
-            Some(path) => path.parent().unwrap_or(Path::new(".")).to_path_buf(),
-            None => PathBuf::from("."),
-        };
-        let file_path = dir.join(SETTINGS_FILE);
+        let file_path = exe_dir().join(SETTINGS_FILE);
 
         Self { file_path }
     }
@@ -65,7 +276,10 @@ impl SettingsManager {
         // 設定ファイルが存在しない場合は、デフォルト値を生成して保存
         if !self.file_path.exists() {
             let defaults = AppSettings::default();
-            self.save(&defaults);
+            // 保存できなくても既定値で起動し、次回の保存時に改めて書き出す
+            if let Err(e) = self.save(&defaults) {
+                println!("{}", e);
+            }
             return defaults;
         }
 
@@ -83,23 +297,178 @@ impl SettingsManager {
                                 .get("max_generations")
                                 .and_then(|s| s.parse().ok())
                                 .unwrap_or(10),
+                            storage_format: section
+                                .get("storage_format")
+                                .unwrap_or(STORAGE_FORMAT_OBJECTS)
+                                .to_string(),
+                            backups_root: section.get("backups_root").unwrap_or("").to_string(),
+                            pre_restore_retention_hours: section
+                                .get("pre_restore_retention_hours")
+                                .and_then(|s| s.parse().ok())
+                                .unwrap_or(default_pre_restore_retention_hours()),
+                            retention_mode: section
+                                .get("retention_mode")
+                                .unwrap_or(RETENTION_MODE_COUNT)
+                                .to_string(),
+                            retention_tiers: {
+                                let defaults = RetentionTiers::default();
+                                let get = |key: &str, default: u64| {
+                                    section
+                                        .get(key)
+                                        .and_then(|s| s.parse().ok())
+                                        .unwrap_or(default)
+                                };
+                                RetentionTiers {
+                                    keep_all_hours: get(
+                                        "retention_keep_all_hours",
+                                        defaults.keep_all_hours,
+                                    ),
+                                    hourly_hours: get(
+                                        "retention_hourly_hours",
+                                        defaults.hourly_hours,
+                                    ),
+                                    daily_days: get("retention_daily_days", defaults.daily_days),
+                                    weekly_months: get(
+                                        "retention_weekly_months",
+                                        defaults.weekly_months,
+                                    ),
+                                }
+                                .clamped()
+                            },
+                            quota_total_mb: section
+                                .get("quota_total_mb")
+                                .and_then(|s| s.parse().ok())
+                                .unwrap_or(0),
+                            quota_subfolder_mb: section
+                                .get("quota_subfolder_mb")
+                                .and_then(|s| s.parse().ok())
+                                .unwrap_or(0),
+                            trash_retention_days: section
+                                .get("trash_retention_days")
+                                .and_then(|s| s.parse().ok())
+                                .unwrap_or(default_trash_retention_days()),
+                            write_wait_max_ms: section
+                                .get("write_wait_max_ms")
+                                .and_then(|s| s.parse().ok())
+                                .unwrap_or(default_write_wait_max_ms()),
+                            debounce_ms: section
+                                .get("debounce_ms")
+                                .and_then(|s| s.parse().ok())
+                                .unwrap_or(default_debounce_ms()),
+                            deletion_quiet_ms: section
+                                .get("deletion_quiet_ms")
+                                .and_then(|s| s.parse().ok())
+                                .unwrap_or(default_deletion_quiet_ms()),
+                            min_backup_interval_minutes: section
+                                .get("min_backup_interval_minutes")
+                                .and_then(|s| s.parse().ok())
+                                .unwrap_or(0),
+                            include_patterns: section
+                                .get("include_patterns")
+                                .map(parse_patterns)
+                                .unwrap_or_default(),
+                            exclude_patterns: section
+                                .get("exclude_patterns")
+                                .map(parse_patterns)
+                                .unwrap_or_default(),
+                            watcher_mode: section
+                                .get("watcher_mode")
+                                .unwrap_or(WATCHER_MODE_NATIVE)
+                                .to_string(),
+                            poll_interval_ms: section
+                                .get("poll_interval_ms")
+                                .and_then(|s| s.parse().ok())
+                                .unwrap_or(default_poll_interval_ms()),
+                            auto_backup_paused: section
+                                .get("auto_backup_paused")
+                                .and_then(|s| s.parse().ok())
+                                .unwrap_or(false),
+                            auto_backup_paused_until: section
+                                .get("auto_backup_paused_until")
+                                .unwrap_or("")
+                                .to_string(),
                         })
                     })
-                    .unwrap_or_else(|| AppSettings::default()); // 失敗時はデフォルト値を返す
-                settings
+                    .unwrap_or_default(); // 失敗時はデフォルト値を返す
+
+                // 手で編集された値なども、画面から保存した場合と同じ範囲に丸める
+                settings.clamped()
             }
             Err(_) => AppSettings::default(),
         }
     }
 
-    pub fn save(&self, settings: &AppSettings) {
+    /// 設定ファイルへ書き出す。書き込めなかった場合は、変更が保存されていないことを呼び出し側へ返します。
+    pub fn save(&self, settings: &AppSettings) -> Result<(), Error> {
         // 設定内容を INI フォーマットでファイルへ書き出し
         let mut ini = Ini::new();
         ini.with_section(Some("Settings"))
             .set("repo_save_path", &settings.repo_save_path)
             .set("theme", &settings.theme)
-            .set("max_generations", settings.max_generations.to_string());
+            .set("max_generations", settings.max_generations.to_string())
+            .set("storage_format", &settings.storage_format)
+            .set("backups_root", &settings.backups_root)
+            .set(
+                "pre_restore_retention_hours",
+                settings.pre_restore_retention_hours.to_string(),
+            )
+            .set("retention_mode", &settings.retention_mode)
+            .set(
+                "retention_keep_all_hours",
+                settings.retention_tiers.keep_all_hours.to_string(),
+            )
+            .set(
+                "retention_hourly_hours",
+                settings.retention_tiers.hourly_hours.to_string(),
+            )
+            .set(
+                "retention_daily_days",
+                settings.retention_tiers.daily_days.to_string(),
+            )
+            .set(
+                "retention_weekly_months",
+                settings.retention_tiers.weekly_months.to_string(),
+            )
+            .set("quota_total_mb", settings.quota_total_mb.to_string())
+            .set(
+                "quota_subfolder_mb",
+                settings.quota_subfolder_mb.to_string(),
+            )
+            .set(
+                "trash_retention_days",
+                settings.trash_retention_days.to_string(),
+            )
+            .set("write_wait_max_ms", settings.write_wait_max_ms.to_string())
+            .set("debounce_ms", settings.debounce_ms.to_string())
+            .set("deletion_quiet_ms", settings.deletion_quiet_ms.to_string())
+            .set(
+                "min_backup_interval_minutes",
+                settings.min_backup_interval_minutes.to_string(),
+            )
+            .set(
+                "include_patterns",
+                settings
+                    .include_patterns
+                    .join(&PATTERN_SEPARATOR.to_string()),
+            )
+            .set(
+                "exclude_patterns",
+                settings
+                    .exclude_patterns
+                    .join(&PATTERN_SEPARATOR.to_string()),
+            )
+            .set("watcher_mode", &settings.watcher_mode)
+            .set("poll_interval_ms", settings.poll_interval_ms.to_string())
+            .set(
+                "auto_backup_paused",
+                settings.auto_backup_paused.to_string(),
+            )
+            .set(
+                "auto_backup_paused_until",
+                &settings.auto_backup_paused_until,
+            );
 
-        let _ = ini.write_to_file(&self.file_path);
+        ini.write_to_file(&self.file_path)
+            .map_err(|e| Error::io("設定ファイルの保存に失敗しました", e))
     }
 }
@@ -0,0 +1,107 @@
+use crate::path_filter::{normalize_patterns, PathFilter};
+use crate::retention::RetentionPolicy;
+use crate::settings_manager::AppSettings;
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// バックアップ先のサブフォルダ直下に置かれる、サブフォルダごとのメタ情報のファイル名
+pub const SUBFOLDER_META_FILE_NAME: &str = "meta.json";
+
+/// サブフォルダごとのメタ情報（Backups/<サブフォルダ>/meta.json）
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct SubfolderMeta {
+    /// ユーザーが設定した自由記述のメモ
+    #[serde(default)]
+    pub memo: String,
+    /// 全体設定の代わりにこのサブフォルダで使用する設定
+    #[serde(default)]
+    pub overrides: SubfolderOverrides,
+}
+
+impl SubfolderMeta {
+    /// meta.json を読み込む（存在しない・壊れている場合は既定値）
+    pub fn load(folder_path: &Path) -> Self {
+        fs::read_to_string(folder_path.join(SUBFOLDER_META_FILE_NAME))
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, folder_path: &Path) -> io::Result<()> {
+        // バックアップがまだない場合でも保持できるよう、親ディレクトリを作成します。
+        fs::create_dir_all(folder_path)?;
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(folder_path.join(SUBFOLDER_META_FILE_NAME), json)
+    }
+}
+
+/// サブフォルダごとに全体設定を上書きする項目。None の項目は全体設定に従います。
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct SubfolderOverrides {
+    /// 保持件数。指定した場合、このサブフォルダは件数による整理を行う
+    #[serde(default)]
+    pub max_generations: Option<usize>,
+    /// 変更検知による自動バックアップを行うかどうか（false の場合は手動バックアップのみ）
+    #[serde(default)]
+    pub auto_backup: Option<bool>,
+    /// 変更イベントをまとめる待ち時間（ミリ秒）。None の場合は全体設定の debounce_ms
+    #[serde(default)]
+    pub debounce_ms: Option<u64>,
+    /// バックアップ対象とするファイルの glob パターン。空でない場合は全体設定の対象パターンの代わりに使用する
+    #[serde(default)]
+    pub included_files: Vec<String>,
+    /// バックアップ対象から除外するファイルの glob パターン（全体設定の除外パターンに追加される）。
+    /// サブフォルダからの相対パス、ファイル名、またはフォルダ（配下すべて）を指定できる
+    #[serde(default)]
+    pub excluded_files: Vec<String>,
+}
+
+impl SubfolderOverrides {
+    /// 設定値を有効な範囲に丸め、対象・除外パターンの空白・重複・不正なものを取り除く
+    pub fn normalized(self) -> Self {
+        Self {
+            max_generations: self.max_generations.map(|n| n.clamp(1, 100)),
+            auto_backup: self.auto_backup,
+            debounce_ms: self.debounce_ms.map(|ms| ms.clamp(50, 60_000)),
+            included_files: normalize_patterns(self.included_files),
+            excluded_files: normalize_patterns(self.excluded_files),
+        }
+    }
+
+    pub fn auto_backup_enabled(&self) -> bool {
+        self.auto_backup.unwrap_or(true)
+    }
+
+    /// このサブフォルダに適用する変更検知の待ち時間（ミリ秒）
+    pub fn debounce_ms(&self, settings: &AppSettings) -> u64 {
+        self.debounce_ms.unwrap_or(settings.debounce_ms)
+    }
+
+    /// このサブフォルダに適用する世代の整理方針
+    pub fn retention_policy(&self, settings: &AppSettings) -> RetentionPolicy {
+        match self.max_generations {
+            Some(limit) => RetentionPolicy::Count(limit),
+            None => settings.retention_policy(),
+        }
+    }
+
+    /// このサブフォルダに適用するファイルのフィルタ。
+    /// 対象パターンはサブフォルダの指定があればそれを、無ければ全体設定を使用し、除外パターンは両方を合わせます。
+    pub fn path_filter(&self, settings: &AppSettings) -> PathFilter {
+        let include = if self.included_files.is_empty() {
+            &settings.include_patterns
+        } else {
+            &self.included_files
+        };
+        let exclude: Vec<String> = settings
+            .exclude_patterns
+            .iter()
+            .chain(&self.excluded_files)
+            .cloned()
+            .collect();
+        PathFilter::new(include, &exclude)
+    }
+}
@@ -0,0 +1,367 @@
+use crate::generation::Generation;
+use crate::subfolder_meta::SUBFOLDER_META_FILE_NAME;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// バックアップルート直下に置かれる、削除した世代・サブフォルダの一時保管フォルダ名
+pub const TRASH_DIR_NAME: &str = ".trash";
+/// ゴミ箱の各項目のフォルダ直下に置かれる、削除時の情報のファイル名
+const TRASH_ENTRY_FILE_NAME: &str = "trash.json";
+
+/// ゴミ箱内の1項目（1回の削除操作で移動した世代、またはサブフォルダ全体）
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct TrashEntry {
+    /// ゴミ箱内での識別子（項目のフォルダ名）
+    #[serde(default)]
+    pub id: String,
+    /// 削除元のサブフォルダ名
+    pub subfolder: String,
+    /// 削除した世代の識別子。サブフォルダごと削除した場合は None
+    pub timestamp: Option<String>,
+    /// 削除した日時（RFC 3339）
+    pub deleted_at: String,
+    /// 含まれる世代の数
+    pub generation_count: usize,
+}
+
+impl TrashEntry {
+    /// 削除からの経過時間が指定した日数を超えているかどうか（削除日時が読めない場合は false）
+    fn is_older_than(&self, days: u64) -> bool {
+        DateTime::parse_from_rfc3339(&self.deleted_at).is_ok_and(|deleted_at| {
+            Local::now().signed_duration_since(deleted_at) > chrono::Duration::days(days as i64)
+        })
+    }
+}
+
+/// 削除した世代を一定期間保管し、元に戻せるようにするゴミ箱。
+/// 各項目は ".trash/<項目ID>/" に、バックアップルートと同じ "<サブフォルダ>/<世代>" の構成で保存されます。
+pub struct Trash {
+    backups_root: PathBuf,
+}
+
+impl Trash {
+    pub fn new(backups_root: &Path) -> Self {
+        Self {
+            backups_root: backups_root.to_path_buf(),
+        }
+    }
+
+    fn trash_dir(&self) -> PathBuf {
+        self.backups_root.join(TRASH_DIR_NAME)
+    }
+
+    /// ゴミ箱内の各項目のフォルダ。
+    /// バックアップルートと同じ構成のため、オブジェクトの参照元の収集にそのまま使用できます。
+    pub fn entry_dirs(backups_root: &Path) -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(backups_root.join(TRASH_DIR_NAME)) else {
+            return Vec::new();
+        };
+        entries
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .filter(|p| p.is_dir())
+            .collect()
+    }
+
+    /// 項目IDに対応するフォルダ。ゴミ箱の外を指す ID は拒否します。
+    fn entry_dir(&self, id: &str) -> io::Result<PathBuf> {
+        if id.is_empty() || id.starts_with('.') || id.contains(['/', '\\']) {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("不正なゴミ箱の項目です: {}", id),
+            ));
+        }
+        Ok(self.trash_dir().join(id))
+    }
+
+    /// 削除日時から新しい項目のフォルダを作成する（同時刻の削除は連番で区別）
+    fn create_entry_dir(&self) -> io::Result<(String, PathBuf)> {
+        let base = Local::now().format("%Y%m%d_%H%M%S_%3f").to_string();
+        let mut id = base.clone();
+        let mut seq = 1;
+        while self.trash_dir().join(&id).exists() {
+            id = format!("{}_{}", base, seq);
+            seq += 1;
+        }
+        let dir = self.trash_dir().join(&id);
+        fs::create_dir_all(&dir)?;
+        Ok((id, dir))
+    }
+
+    fn save_entry(dir: &Path, entry: &TrashEntry) -> io::Result<()> {
+        let json = serde_json::to_string_pretty(entry)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        fs::write(dir.join(TRASH_ENTRY_FILE_NAME), json)
+    }
+
+    /// 移動に失敗した項目のフォルダを片付ける（残っても項目情報が無いため一覧には表示されない）
+    fn discard_entry_dir(dir: &Path) {
+        if let Err(e) = fs::remove_dir_all(dir) {
+            println!(
+                "ゴミ箱の作業用フォルダの削除に失敗しました: {} ({:?})",
+                dir.display(),
+                e
+            );
+        }
+    }
+
+    fn load_entry(&self, id: &str) -> io::Result<TrashEntry> {
+        let dir = self.entry_dir(id)?;
+        let content = fs::read_to_string(dir.join(TRASH_ENTRY_FILE_NAME))?;
+        let mut entry: TrashEntry = serde_json::from_str(&content)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        entry.id = id.to_string();
+        Ok(entry)
+    }
+
+    /// 世代（メタ情報を含む）をゴミ箱へ移動する。
+    /// サブフォルダの meta.json は、そのサブフォルダの設定として残したままにします。
+    pub fn move_generation(
+        &self,
+        subfolder: &str,
+        generation: &Generation,
+    ) -> io::Result<TrashEntry> {
+        let (id, dir) = self.create_entry_dir()?;
+        let entry = TrashEntry {
+            id,
+            subfolder: subfolder.to_string(),
+            timestamp: Some(generation.id.clone()),
+            deleted_at: Local::now().to_rfc3339(),
+            generation_count: 1,
+        };
+        // 項目情報を先に書き込み、移動後に失敗して項目情報の無い世代が残らないようにする
+        if let Err(e) =
+            Self::save_entry(&dir, &entry).and_then(|_| generation.move_to(&dir.join(subfolder)))
+        {
+            Self::discard_entry_dir(&dir);
+            return Err(e);
+        }
+        Ok(entry)
+    }
+
+    /// サブフォルダ（全世代と meta.json）をまとめてゴミ箱へ移動する
+    pub fn move_subfolder(&self, subfolder: &str) -> io::Result<TrashEntry> {
+        let folder_path = self.backups_root.join(subfolder);
+        let generation_count = Generation::list(&folder_path).len();
+
+        let (id, dir) = self.create_entry_dir()?;
+        let entry = TrashEntry {
+            id,
+            subfolder: subfolder.to_string(),
+            timestamp: None,
+            deleted_at: Local::now().to_rfc3339(),
+            generation_count,
+        };
+        if let Err(e) = Self::save_entry(&dir, &entry)
+            .and_then(|_| fs::rename(&folder_path, dir.join(subfolder)))
+        {
+            Self::discard_entry_dir(&dir);
+            return Err(e);
+        }
+        Ok(entry)
+    }
+
+    /// ゴミ箱内の項目を、削除日時の新しい順で返す
+    pub fn list(&self) -> Vec<TrashEntry> {
+        let mut entries: Vec<TrashEntry> = Self::entry_dirs(&self.backups_root)
+            .iter()
+            .filter_map(|dir| {
+                let id = dir.file_name()?.to_string_lossy().to_string();
+                self.load_entry(&id).ok()
+            })
+            .collect();
+        entries.sort_by(|a, b| b.deleted_at.cmp(&a.deleted_at));
+        entries
+    }
+
+    /// 項目を元のサブフォルダへ戻す。
+    /// 同じ識別子の世代が既にある場合は、何も移動せずにエラーを返します。
+    pub fn restore(&self, id: &str) -> io::Result<TrashEntry> {
+        let entry = self.load_entry(id)?;
+        let dir = self.entry_dir(id)?;
+        let src = dir.join(&entry.subfolder);
+        let dest = self.backups_root.join(&entry.subfolder);
+
+        let children: Vec<PathBuf> = fs::read_dir(&src)?
+            .filter_map(|e| e.ok())
+            .map(|e| e.path())
+            .collect();
+
+        // 1. 先にすべての衝突を確認し、途中まで戻した状態で失敗しないようにする
+        for child in &children {
+            let name = child.file_name().unwrap_or_default();
+            if name != SUBFOLDER_META_FILE_NAME && dest.join(name).exists() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!(
+                        "同じ名前のバックアップが既に存在します: {}",
+                        name.to_string_lossy()
+                    ),
+                ));
+            }
+        }
+
+        // 2. 移動（meta.json は、削除後に作り直されたものがあればそちらを優先する）
+        fs::create_dir_all(&dest)?;
+        for child in &children {
+            let name = child.file_name().unwrap_or_default();
+            let target = dest.join(name);
+            if name == SUBFOLDER_META_FILE_NAME && target.exists() {
+                continue;
+            }
+            fs::rename(child, target)?;
+        }
+
+        fs::remove_dir_all(&dir)?;
+        Ok(entry)
+    }
+
+    /// 項目を完全に削除する（オブジェクトの回収は呼び出し側で行う）
+    pub fn remove(&self, id: &str) -> io::Result<()> {
+        let dir = self.entry_dir(id)?;
+        if !dir.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("ゴミ箱の項目が見つかりません: {}", id),
+            ));
+        }
+        fs::remove_dir_all(dir)
+    }
+
+    /// ゴミ箱を空にし、削除した項目数を返す
+    pub fn empty(&self) -> io::Result<usize> {
+        let dirs = Self::entry_dirs(&self.backups_root);
+        for dir in &dirs {
+            fs::remove_dir_all(dir)?;
+        }
+        Ok(dirs.len())
+    }
+
+    /// 削除から指定した日数が経過した項目を完全に削除し、削除した項目数を返す
+    pub fn purge_older_than(&self, days: u64) -> io::Result<usize> {
+        let mut removed = 0;
+        for entry in self.list() {
+            if entry.is_older_than(days) {
+                self.remove(&entry.id)?;
+                removed += 1;
+            }
+        }
+        Ok(removed)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generation::GenerationMeta;
+
+    const SUBFOLDER: &str = "Save1";
+    const GENERATION_ID: &str = "20240101_120000_000";
+
+    /// バックアップルートに、メモ付きの世代1つと meta.json を持つサブフォルダを作成する
+    fn setup() -> (tempfile::TempDir, Generation) {
+        let root = tempfile::tempdir().unwrap();
+        let folder_path = root.path().join(SUBFOLDER);
+        let generation_dir = folder_path.join(GENERATION_ID);
+        fs::create_dir_all(&generation_dir).unwrap();
+        fs::write(generation_dir.join("save.dat"), "data").unwrap();
+        fs::write(folder_path.join(SUBFOLDER_META_FILE_NAME), "subfolder meta").unwrap();
+
+        let generation = Generation::find(&folder_path, GENERATION_ID).unwrap();
+        let meta = GenerationMeta {
+            memo: "memo".to_string(),
+            ..Default::default()
+        };
+        generation.save_meta(&meta).unwrap();
+        (root, generation)
+    }
+
+    #[test]
+    fn generation_round_trip_keeps_meta_json() {
+        let (root, generation) = setup();
+        let trash = Trash::new(root.path());
+        let folder_path = root.path().join(SUBFOLDER);
+
+        let entry = trash.move_generation(SUBFOLDER, &generation).unwrap();
+        assert_eq!(entry.timestamp.as_deref(), Some(GENERATION_ID));
+        assert!(Generation::list(&folder_path).is_empty());
+        // サブフォルダのメモ・設定は、世代が無くなってもサブフォルダに残る
+        assert_eq!(
+            fs::read_to_string(folder_path.join(SUBFOLDER_META_FILE_NAME)).unwrap(),
+            "subfolder meta"
+        );
+        assert_eq!(trash.list().len(), 1);
+
+        trash.restore(&entry.id).unwrap();
+        let restored = Generation::find(&folder_path, GENERATION_ID).unwrap();
+        assert_eq!(
+            fs::read_to_string(restored.path.join("save.dat")).unwrap(),
+            "data"
+        );
+        assert_eq!(restored.load_meta().memo, "memo");
+        assert_eq!(
+            fs::read_to_string(folder_path.join(SUBFOLDER_META_FILE_NAME)).unwrap(),
+            "subfolder meta"
+        );
+        assert!(trash.list().is_empty());
+    }
+
+    #[test]
+    fn subfolder_round_trip_prefers_recreated_meta_json() {
+        let (root, _) = setup();
+        let trash = Trash::new(root.path());
+        let folder_path = root.path().join(SUBFOLDER);
+
+        let entry = trash.move_subfolder(SUBFOLDER).unwrap();
+        assert_eq!(entry.generation_count, 1);
+        assert!(!folder_path.exists());
+
+        // 削除後に作り直された meta.json は、復元時に上書きしない
+        fs::create_dir_all(&folder_path).unwrap();
+        fs::write(folder_path.join(SUBFOLDER_META_FILE_NAME), "recreated").unwrap();
+
+        trash.restore(&entry.id).unwrap();
+        assert!(Generation::find(&folder_path, GENERATION_ID).is_some());
+        assert_eq!(
+            fs::read_to_string(folder_path.join(SUBFOLDER_META_FILE_NAME)).unwrap(),
+            "recreated"
+        );
+        assert!(trash.list().is_empty());
+    }
+
+    #[test]
+    fn restore_conflict_moves_nothing() {
+        let (root, generation) = setup();
+        let trash = Trash::new(root.path());
+        let folder_path = root.path().join(SUBFOLDER);
+
+        let entry = trash.move_generation(SUBFOLDER, &generation).unwrap();
+        fs::create_dir_all(folder_path.join(GENERATION_ID)).unwrap();
+
+        let err = trash.restore(&entry.id).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::AlreadyExists);
+        assert_eq!(trash.list().len(), 1);
+    }
+
+    #[test]
+    fn failed_move_leaves_generation_in_place() {
+        let (root, _) = setup();
+        let trash = Trash::new(root.path());
+        let folder_path = root.path().join(SUBFOLDER);
+        // メタ情報は移動できるが、世代本体の移動には失敗する状況
+        let missing = Generation {
+            path: folder_path.join("20240101_130000_000"),
+            ..Generation::find(&folder_path, GENERATION_ID).unwrap()
+        };
+
+        assert!(trash.move_generation(SUBFOLDER, &missing).is_err());
+        // 後片付けで項目フォルダが消え、先に移動したメタ情報も元に戻る
+        assert!(trash.list().is_empty());
+        assert!(Trash::entry_dirs(root.path()).is_empty());
+        let generation = Generation::find(&folder_path, GENERATION_ID).unwrap();
+        assert_eq!(generation.load_meta().memo, "memo");
+    }
+}
@@ -0,0 +1,140 @@
+use crate::settings_manager::AppSettings;
+use chrono::Local;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+
+/// 自動バックアップで変更がなかった場合に、同じサブフォルダへ「変更なし」を再び通知するまでの間隔
+const UNCHANGED_NOTIFY_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+/// 監視の状態
+#[derive(Serialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WatcherStatus {
+    /// セーブデータフォルダの変更を監視中
+    Running,
+    /// 自動バックアップを一時停止中
+    Paused,
+    /// セーブデータフォルダが存在しないため、作成されるまで親フォルダを監視中
+    RootMissing,
+    /// 監視の開始に失敗した、または監視中にエラーが発生した
+    Errored,
+    /// 監視していない（起動直後など）
+    Stopped,
+}
+
+/// サブフォルダごとの直近のバックアップの結果
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct SubfolderHealth {
+    /// 最後にバックアップに成功した日時（RFC 3339）。変更がなく世代を作らなかった場合も含む
+    pub last_success: Option<String>,
+    /// 最後のバックアップが失敗した場合の理由（次に成功すると None に戻る）
+    pub last_error: Option<String>,
+}
+
+/// 監視と自動バックアップの状態（"watcher-state" イベントのペイロード）
+#[derive(Serialize, Clone, Debug)]
+pub struct WatcherState {
+    pub status: WatcherStatus,
+    /// 自動バックアップが一時停止中かどうか
+    pub paused: bool,
+    /// 一時停止が自動的に解除される日時（RFC 3339）。再開するまで停止する場合は None
+    pub paused_until: Option<String>,
+    /// 実際に使用している監視方式（"native" / "poll"）。監視していない場合は None
+    pub mode: Option<String>,
+    /// 監視のエラーの内容（status が "errored" の場合）
+    pub error: Option<String>,
+    /// サブフォルダ名ごとの直近のバックアップの結果
+    pub subfolders: BTreeMap<String, SubfolderHealth>,
+}
+
+/// 監視スレッドとバックアップ処理が記録する、監視の健全性。
+/// リリースビルドではコンソールが無いため、ここに記録した内容を画面へ通知して異常に気付けるようにします。
+#[derive(Default)]
+pub struct WatcherHealth {
+    mode: Option<String>,
+    root_missing: bool,
+    error: Option<String>,
+    subfolders: BTreeMap<String, SubfolderHealth>,
+    /// サブフォルダごとに、自動バックアップの「変更なし」を最後に通知した時刻
+    unchanged_notified: HashMap<String, Instant>,
+}
+
+impl WatcherHealth {
+    /// ウォッチャーを開始したことを記録する（root_missing はセーブデータフォルダの作成を待っている場合）
+    pub fn set_started(&mut self, mode: &str, root_missing: bool) {
+        self.mode = Some(mode.to_string());
+        self.root_missing = root_missing;
+        self.error = None;
+    }
+
+    /// 監視の開始に失敗した、または監視中にエラーが発生したことを記録する。
+    /// stopped が true の場合は、ウォッチャーが動いていないものとして扱います。
+    /// 正常な状態からエラーになった場合のみ true を返します（通知を繰り返さないため）。
+    pub fn set_error(&mut self, reason: String, stopped: bool) -> bool {
+        if stopped {
+            self.mode = None;
+        }
+        self.error.replace(reason).is_none()
+    }
+
+    /// 監視中のエラーが解消された（正常なイベントを受信した）ことを記録する。エラーを解除した場合は true
+    pub fn clear_error(&mut self) -> bool {
+        self.mode.is_some() && self.error.take().is_some()
+    }
+
+    pub fn record_success(&mut self, subfolder: &str) {
+        let entry = self.subfolders.entry(subfolder.to_string()).or_default();
+        entry.last_success = Some(Local::now().to_rfc3339());
+        entry.last_error = None;
+    }
+
+    pub fn record_failure(&mut self, subfolder: &str, reason: String) {
+        let entry = self.subfolders.entry(subfolder.to_string()).or_default();
+        entry.last_error = Some(reason);
+    }
+
+    /// 自動バックアップで変更がなかったことを通知するかどうか。
+    /// ゲームが同じ内容を繰り返し保存しても通知が続かないよう、サブフォルダごとに一定間隔で1回に抑えます。
+    pub fn should_notify_unchanged(&mut self, subfolder: &str) -> bool {
+        let now = Instant::now();
+        match self.unchanged_notified.get(subfolder) {
+            Some(last) if now.duration_since(*last) < UNCHANGED_NOTIFY_INTERVAL => false,
+            _ => {
+                self.unchanged_notified.insert(subfolder.to_string(), now);
+                true
+            }
+        }
+    }
+
+    /// 起動時に、既存の世代から最後にバックアップした日時を設定する（記録済みの場合は変更しない）
+    pub fn seed_last_success(&mut self, subfolder: &str, at: String) {
+        let entry = self.subfolders.entry(subfolder.to_string()).or_default();
+        entry.last_success.get_or_insert(at);
+    }
+
+    /// 現在の設定（一時停止の状態）と合わせて、画面へ通知する状態を作成する
+    pub fn state(&self, settings: &AppSettings) -> WatcherState {
+        let paused = settings.is_auto_backup_paused();
+        let status = if self.error.is_some() {
+            WatcherStatus::Errored
+        } else if self.mode.is_none() {
+            WatcherStatus::Stopped
+        } else if self.root_missing {
+            WatcherStatus::RootMissing
+        } else if paused {
+            WatcherStatus::Paused
+        } else {
+            WatcherStatus::Running
+        };
+        WatcherState {
+            status,
+            paused,
+            paused_until: (paused && !settings.auto_backup_paused_until.is_empty())
+                .then(|| settings.auto_backup_paused_until.clone()),
+            mode: self.mode.clone(),
+            error: self.error.clone(),
+            subfolders: self.subfolders.clone(),
+        }
+    }
+}
@@ -1,6 +1,7 @@
 import { createRouter, createWebHashHistory } from 'vue-router'
 import SaveList from '../components/SaveList.vue'
 import Settings from '../components/Settings.vue'
+import Trash from '../components/Trash.vue'
 import NotificationOverlay from '../components/NotificationOverlay.vue'
 
 const router = createRouter({
@@ -16,6 +17,11 @@ const router = createRouter({
       name: 'settings',
       component: Settings
     },
+    {
+      path: '/trash',
+      name: 'trash',
+      component: Trash
+    },
     {
       path: '/notification',
       name: 'notification',
@@ -3,18 +3,84 @@ import { invoke } from '@tauri-apps/api/core'
 import { listen } from '@tauri-apps/api/event'
 import dayjs from 'dayjs'
 
+// バックエンドのエラーの種類ごとの見出しと、対処のヒント
+const errorKinds = {
+  io: { title: 'ファイルの読み書きに失敗しました', hint: 'ディスクの空き容量と、フォルダへのアクセス権限を確認してください' },
+  not_found: { title: '対象が見つかりません', hint: '既に削除・移動された可能性があります。一覧を確認してください' },
+  invalid_name: { title: '使用できない名前です', hint: 'フォルダ名に "/" "\\" ":" を含めたり、"." で始めたりすることはできません' },
+  locked: { title: '操作できません', hint: '固定を解除するか、ファイルを使用しているゲームなどを終了してから再度お試しください' },
+  corrupt_backup: { title: 'バックアップが破損しています', hint: '整合性チェックで確認し、別の時点のバックアップを使用してください' },
+  already_exists: { title: '既に存在します', hint: '同じ項目が既にあるため、先に整理してから再度お試しください' },
+  invalid_argument: { title: '入力内容が正しくありません', hint: '指定した値を確認してください' },
+  cancelled: { title: '処理を取り消しました', hint: '' },
+  internal: { title: '予期しないエラーが発生しました', hint: '繰り返し発生する場合は、アプリを再起動してください' }
+}
+
+// invoke の失敗理由を { kind, title, message, hint } に揃える
+// （コマンドは { kind, message } で reject される。それ以外の形式は internal として扱う）
+export const describeError = (e) => {
+  const kind = errorKinds[e?.kind] ? e.kind : 'internal'
+  const message = typeof e === 'string' ? e : (e?.message ?? String(e))
+  return { kind, message, ...errorKinds[kind] }
+}
+
 export const useMainStore = defineStore('main', {
   state: () => ({
     settings: {
       repo_save_path: '',
       theme: 'system',
-      max_generations: 10
+      max_generations: 10,
+      storage_format: 'objects',
+      backups_root: '',
+      pre_restore_retention_hours: 24,
+      retention_mode: 'count',
+      retention_tiers: { keep_all_hours: 1, hourly_hours: 24, daily_days: 7, weekly_months: 3 },
+      quota_total_mb: 0,
+      quota_subfolder_mb: 0,
+      trash_retention_days: 30,
+      write_wait_max_ms: 10000,
+      debounce_ms: 300,
+      deletion_quiet_ms: 500,
+      min_backup_interval_minutes: 0,
+      include_patterns: [],
+      exclude_patterns: ['*.tmp', '*.bak', '*.log'],
+      watcher_mode: 'native',
+      poll_interval_ms: 2000
     },
-    items: [], // [{ name, memo, backups: [{timestamp, timestamp_raw}], source_exists }]
-    isConnected: false
+    items: [], // [{ name, memo, overrides, backups: [{timestamp, timestamp_raw, integrity, origin, file_count, total_size, memo, tags, pinned, possibly_incomplete}], source_exists }]
+    trash: [], // [{ id, subfolder, timestamp, deleted_at, generation_count }] 新しい順
+    // 監視の状態（status: running / paused / root_missing / errored / stopped）と、サブフォルダごとの直近のバックアップの結果
+    watcher: { status: 'stopped', paused: false, paused_until: null, mode: null, error: null, subfolders: {} },
+    // バックアップ・リストアなどのジョブキュー（kind: auto_backup / manual_backup / restore / delete / prune / migration / annotate / verify）
+    queue: { running: null, pending: [] }, // running/pending の各要素: { id, kind, subfolder, queued_at }
+    isConnected: false,
+    // 実行中のバックアップ・リストア・移行の進捗（operation: backup / restore / migration）
+    progress: null, // { job_id, operation, subfolder, status, files_done, files_total, bytes_done, bytes_total }
+    migration: null, // バックアップフォルダ移行中の進捗（progress と同じ形式）
+    // 直近に失敗した操作のエラー（App.vue のスナックバーに表示し、閉じると null に戻す）
+    error: null // { kind, title, message, hint }
   }),
 
   actions: {
+    async call(command, args = {}, { notify = true } = {}) {
+      // コマンドを呼び出し、失敗した場合は describeError の形式で reject する
+      // notify が true の場合は error にも記録して画面全体へ表示する（取り消しは利用者の操作のため表示しない）
+      try {
+        return await invoke(command, args)
+      } catch (e) {
+        const error = describeError(e)
+        console.error(`${command} に失敗しました:`, error)
+        if (notify && error.kind !== 'cancelled') {
+          this.error = error
+        }
+        throw error
+      }
+    },
+
+    clearError() {
+      this.error = null
+    },
+
     async init() {
       // バックグラウンド側（Rust）からの設定変更や状態の同期イベントを常時待受
       await listen('settings-state', (event) => {
@@ -25,6 +91,27 @@ export const useMainStore = defineStore('main', {
         this.items = event.payload
       })
 
+      await listen('trash-state', (event) => {
+        this.trash = event.payload
+      })
+
+      await listen('watcher-state', (event) => {
+        this.watcher = event.payload
+      })
+
+      await listen('queue-state', (event) => {
+        this.queue = event.payload
+      })
+
+      await listen('operation-progress', (event) => {
+        // 完了・取り消し・失敗の通知を受けたら進捗の表示を消す
+        const running = event.payload.status === 'running'
+        this.progress = running ? event.payload : null
+        if (event.payload.operation === 'migration' && this.migration) {
+          this.migration = event.payload
+        }
+      })
+
       // システムトレイ側からの画面遷移要求をリッスン
       await listen('navigate-settings', () => {
         // App.vue 側などで activeTab を切り替えるためのトリガー
@@ -32,45 +119,168 @@ export const useMainStore = defineStore('main', {
 
       // 初期状態をリクエスト
       try {
-        await invoke('initialize_app')
+        await this.call('initialize_app')
         this.isConnected = true
       } catch (e) {
-        console.error("初期化に失敗しました", e)
+        // 理由は error に記録され、画面に表示される
       }
     },
 
-    async saveSettings(newPath, maxGenerations, theme) {
-      // ユーザー設定（パス、保持世代、テーマ）をバックグラウンドへ保存
+    async saveSettings(newPath, maxGenerations, theme, storageFormat) {
+      // ユーザー設定（パス、保持世代、テーマ、保存形式）をバックグラウンドへ保存
       // 引数が未指定の場合は現在のストアの値をデフォルトとして採用
-      await invoke('save_settings', {
+      await this.call('save_settings', {
         repoPath: newPath,
         maxGenerations: maxGenerations || this.settings.max_generations || 10,
-        theme: theme || this.settings.theme || 'system'
+        theme: theme || this.settings.theme || 'system',
+        storageFormat: storageFormat || this.settings.storage_format || 'objects'
       })
     },
 
+    async pauseWatching(minutes = null) {
+      // 自動バックアップを一時停止（minutes を指定した場合はその時間の経過後に自動で再開）
+      // 状態は watcher-state イベントで反映される
+      await this.call('pause_watching', { minutes })
+    },
+
+    async resumeWatching() {
+      await this.call('resume_watching')
+    },
+
+    async fetchWatcherState() {
+      // 監視の状態を再取得（通常は watcher-state イベントで自動的に反映される）
+      this.watcher = await this.call('get_watcher_state')
+    },
+
+    async cancelJob(jobId) {
+      // 待機中のジョブを取り消す、または実行中の処理を中断する（途中までの内容は削除される）
+      await this.call('cancel_job', { jobId })
+    },
+
+    async fetchQueueState() {
+      // ジョブキューの状態を再取得（通常は queue-state イベントで自動的に反映される）
+      this.queue = await this.call('get_queue_state')
+    },
+
+    async updateSettings(patch) {
+      // 指定した設定項目のみを変更して保存（未指定の項目はバックエンド側で現在値が維持される）
+      // patch のキーは save_settings の引数名（camelCase）。例: { preRestoreRetentionHours: 48 }
+      await this.call('save_settings', {
+        repoPath: this.settings.repo_save_path,
+        ...patch
+      })
+    },
+
+    async previewRetention(policy = {}, subfolderName = null) {
+      // 整理方針を適用した場合に削除される世代を取得する（実際には削除しない）
+      // policy は { retentionMode, retentionTiers, maxGenerations }。省略した項目は現在の設定値
+      return await this.call('preview_retention', { subfolderName, ...policy })
+    },
+
+    async migrateBackupsRoot(newRoot) {
+      // バックアップの保存先を変更し、既存のバックアップを新しい場所へ移動する
+      // 失敗時は describeError の形式で reject され（設定画面に表示する）、保存先は変更されない
+      this.migration = { status: 'running', files_done: 0, files_total: 0 }
+      try {
+        await this.call('migrate_backups_root', { newRoot }, { notify: false })
+      } finally {
+        this.migration = null
+      }
+    },
+
     async triggerBackup(subfolderName) {
-      // 特定のサブフォルダに対して即座にバックアップを実行させる
-      await invoke('manual_backup', { subfolderName })
+      // 特定のサブフォルダのバックアップをキューで実行し、完了まで待つ（実行中のジョブがあれば、その完了後に実行される）
+      // 失敗・取り消しの場合は describeError の形式で reject される
+      await this.call('manual_backup', { subfolderName })
     },
 
     async restore(subfolderName, timestamp) {
       // 指定したタイムスタンプのバックアップを元の場所へ書き戻す
-      await invoke('restore_backup', { subfolderName, timestamp })
+      // 失敗時は describeError の形式で reject される（現在のセーブデータはそのまま残る）
+      await this.call('restore_backup', { subfolderName, timestamp })
+    },
+
+    async undoLastRestore(subfolderName) {
+      // 直近のリストアを取り消し、リストア直前に自動保存されたセーブデータへ戻す
+      await this.call('undo_last_restore', { subfolderName })
+    },
+
+    async deleteBackup(subfolderName, timestamp, force = false) {
+      // 固定された世代は force を指定しない限り削除されない（locked で reject される）
+      await this.call('delete_backup', { subfolderName, timestamp, force })
     },
 
-    async deleteBackup(subfolderName, timestamp) {
-      await invoke('delete_backup', { subfolderName, timestamp })
+    async setPinned(subfolderName, timestamp, pinned) {
+      // 世代を固定すると、世代制限による自動削除の対象外となる
+      await this.call('set_generation_pinned', { subfolderName, timestamp, pinned })
     },
 
     async deleteSubfolder(subfolderName) {
-      await invoke('delete_subfolder', { subfolderName })
+      // サブフォルダのすべての世代をゴミ箱へ移動する
+      await this.call('delete_subfolder', { subfolderName })
+    },
+
+    async fetchTrash() {
+      // ゴミ箱の内容を再取得（保管期間を過ぎた項目はこの時点で完全に削除される）
+      this.trash = await this.call('list_trash')
+    },
+
+    async restoreFromTrash(trashId) {
+      // ゴミ箱の項目を元のサブフォルダへ戻す。同じ世代が既にある場合は already_exists で reject される
+      // （理由はゴミ箱の画面に表示する）
+      await this.call('restore_from_trash', { trashId }, { notify: false })
+    },
+
+    async emptyTrash(trashId = null) {
+      // ゴミ箱の項目を完全に削除する（trashId を省略した場合はすべて）
+      await this.call('empty_trash', { trashId })
+    },
+
+    async verifyBackups(subfolderName = null, timestamp = null) {
+      // 世代の内容をマニフェストと突き合わせて検証し、結果（欠落・余分・破損ファイル）を返す
+      // 引数を省略した場合はすべてのサブフォルダ・世代が対象
+      return await this.call('verify_backups', { subfolderName, timestamp })
     },
 
     async saveMemo(subfolderName, content) {
       // サブフォルダに対するメモを保存
       // バックエンド側で meta.json への書き出しが行われる
-      await invoke('save_memo', { subfolderName, memoContent: content })
+      await this.call('save_memo', { subfolderName, memoContent: content })
+    },
+
+    async setSubfolderOverrides(subfolderName, overrides) {
+      // サブフォルダごとの設定を保存（null の項目は全体設定に従う）
+      // overrides: { max_generations, auto_backup, debounce_ms, included_files, excluded_files }
+      await this.call('set_subfolder_overrides', { subfolderName, overrides })
+    },
+
+    async setGenerationMemo(subfolderName, timestamp, memo) {
+      // 特定の世代（時点）に対するメモを保存
+      await this.call('set_generation_memo', { subfolderName, timestamp, memo })
+    },
+
+    async setGenerationTags(subfolderName, timestamp, tags) {
+      // 特定の世代のタグを置き換える
+      await this.call('set_generation_tags', { subfolderName, timestamp, tags })
+    },
+
+    async clearGenerationAnnotations(subfolderName, timestamp) {
+      // 特定の世代のメモとタグをすべて消去
+      await this.call('clear_generation_annotations', { subfolderName, timestamp })
+    },
+
+    async openFolder(path) {
+      // フォルダをエクスプローラで開く（存在しない場合は not_found で reject される）
+      await this.call('open_path_in_explorer', { path })
+    },
+
+    async openBackupsFolder() {
+      await this.call('open_backups_folder')
+    },
+
+    async sendTestNotification() {
+      // 開発ビルドのみ使用できるデバッグ用のコマンド
+      await this.call('test_notification')
     }
   }
 })